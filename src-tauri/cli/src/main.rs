@@ -0,0 +1,192 @@
+//! 命令行版本，与桌面版共用索引、检索及配置，便于在服务器上创建索引或在 CI 中测试
+
+use std::path::PathBuf;
+use std::process::exit;
+
+use clap::{Parser, Subcommand};
+use word_index_core::structs::SearchOptions;
+use word_index_core::{CommandError, Config, Result, SearchState};
+use word_index_stdio::mcp::Mcp;
+use word_index_stdio::rpc::Rpc;
+
+#[derive(Parser)]
+#[clap(name = "word-index-cli", version, about = "word-index 命令行工具")]
+struct Cli {
+    /// 工作目录，配置文件及索引数据均位于该目录下，默认为当前目录
+    #[clap(short = 'C', long, env = "WORD_INDEX_DIR")]
+    dir: Option<PathBuf>,
+
+    #[clap(subcommand)]
+    command: Commands,
+}
+
+#[derive(Subcommand)]
+enum Commands {
+    /// 为指定路径的文件创建索引，并将该路径加入配置
+    Index { path: String },
+    /// 检索文档
+    Search {
+        query: String,
+        /// 文档类型，可指定多个
+        #[clap(long)]
+        class: Vec<String>,
+        /// 返回的文档数，至少为 1
+        #[clap(
+            long,
+            default_value_t = 10,
+            value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..)
+        )]
+        limit: usize,
+        #[clap(long, default_value_t = 0)]
+        offset: usize,
+        /// 以 JSON 格式输出
+        #[clap(long)]
+        json: bool,
+    },
+    /// 索引统计
+    Stats {
+        /// 以 JSON 格式输出
+        #[clap(long)]
+        json: bool,
+    },
+    /// 在 127.0.0.1 上启动 HTTP 接口
+    Serve {
+        /// 端口，默认使用配置中的端口
+        #[clap(long)]
+        port: Option<u16>,
+        /// 网页版目录（前端构建产物），指定后同时提供只读的网页版检索页面，默认使用配置中的目录
+        #[clap(long)]
+        web_root: Option<PathBuf>,
+    },
+    /// 在标准输入输出上提供 MCP 服务，供本地 AI 助手检索文档
+    Mcp,
+    /// 在标准输入输出上提供按行分隔的 JSON-RPC 服务，供编辑器插件调用
+    Rpc,
+    /// 删除文件已不存在的文档
+    Purge {
+        /// 清空全部索引
+        #[clap(long)]
+        all: bool,
+    },
+}
+
+fn main() {
+    let cli = Cli::parse();
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .expect("创建运行时失败");
+    if let Err(e) = runtime.block_on(run(cli)) {
+        eprintln!("{e}");
+        exit(1);
+    }
+}
+
+async fn run(cli: Cli) -> Result<()> {
+    if let Some(dir) = &cli.dir {
+        std::env::set_current_dir(dir).map_err(|e| {
+            CommandError::new("cli.ChangeDir", format!("{}\n{e}", dir.display()))
+                .with_detail("path", dir.display())
+        })?;
+    }
+    let config = Config::load().await?;
+    // 提示输出到标准错误，不影响 mcp、rpc 子命令的标准输出
+    let state = SearchState::new(&config).on_alerts(|alerts| {
+        for alert in alerts {
+            eprintln!("保存的检索「{}」有 {} 个新文档", alert.name, alert.total);
+        }
+    });
+    if let Some(e) = &state.startup.tokenizer_error {
+        eprintln!("无法加载用户词典或停用词，已使用内置词典：{e}");
+    }
+    if state.startup.rebuilt && !config.paths.is_empty() {
+        eprintln!("索引结构已变更，正在为所有路径重新创建索引……");
+        state.reindex(&config).await?;
+    }
+
+    match cli.command {
+        Commands::Index { path } => {
+            // 与桌面版一致使用绝对路径，文档 id 由路径生成，且其他工作目录下也能打开文件
+            let path = absolute_path(&path)?;
+            let added = state.index(path.clone()).await?;
+            Config::update(|config| {
+                if !config.paths.contains(&path) {
+                    config.paths.push(path);
+                }
+            })
+            .await?;
+            println!("已索引 {added} 个文档");
+        }
+        Commands::Search {
+            query,
+            class,
+            limit,
+            offset,
+            json,
+        } => {
+            let classes = if class.is_empty() { None } else { Some(class) };
+            let options = SearchOptions::default();
+            let fruit = state.search(query, offset, limit, classes, &options, &config)?;
+            if json {
+                println!("{}", to_json(&fruit)?);
+            } else {
+                for hit in &fruit.results {
+                    println!("{}", hit.doc.path);
+                }
+                println!("共 {} 个文档", fruit.total);
+            }
+        }
+        Commands::Stats { json } => {
+            let stats = state.stats()?;
+            if json {
+                println!("{}", to_json(&stats)?);
+            } else {
+                println!("文档：{}", stats.docs);
+                println!("段：{}", stats.segments);
+                for class in &stats.classes {
+                    println!("类型 {}：{}", class.value, class.count);
+                }
+                for folder in &stats.folders {
+                    println!("目录 {}：{}", folder.value, folder.count);
+                }
+            }
+        }
+        Commands::Serve { port, web_root } => {
+            let port = port.unwrap_or(config.server.port);
+            let web_root = web_root.or_else(|| config.server.web_root.clone().map(PathBuf::from));
+            word_index_server::serve(state, port, web_root).await?;
+        }
+        Commands::Mcp => {
+            Mcp::new(state).serve().await?;
+        }
+        Commands::Rpc => {
+            Rpc::new(state).serve().await?;
+        }
+        Commands::Purge { all } => {
+            let removed = state.purge(all)?;
+            println!("已删除 {removed} 个文档");
+        }
+    }
+    Ok(())
+}
+
+fn absolute_path(path: &str) -> Result<String> {
+    let absolute = std::fs::canonicalize(path).map_err(|e| {
+        CommandError::new("cli.Canonicalize", format!("{path}\n{e}")).with_detail("path", path)
+    })?;
+    let absolute = absolute.to_string_lossy();
+    // Windows 上 canonicalize 返回 \\?\D:\ 形式，去掉前缀以与桌面版选择的路径一致
+    #[cfg(windows)]
+    if let Some(disk) = absolute
+        .strip_prefix(r"\\?\")
+        .filter(|p| !p.starts_with("UNC"))
+    {
+        return Ok(disk.to_string());
+    }
+    Ok(absolute.into_owned())
+}
+
+fn to_json<T: serde::Serialize>(value: &T) -> Result<String> {
+    serde_json::to_string_pretty(value)
+        .map_err(|e| CommandError::new("cli.EncodeJson", e.to_string()))
+}
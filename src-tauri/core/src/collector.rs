@@ -0,0 +1,155 @@
+use std::collections::HashMap;
+
+use tantivy::{
+    collector::{Collector, SegmentCollector},
+    columnar::{Column, StrColumn},
+    DocId, Score, SegmentOrdinal, SegmentReader,
+};
+use time::{OffsetDateTime, UtcOffset};
+use tracing::error;
+
+use crate::structs::{FacetCount, Facets};
+
+/// 统计命中文档的类型、顶层目录、修改年份分布
+pub struct FacetCounter {
+    offset: UtcOffset,
+}
+
+impl Default for FacetCounter {
+    fn default() -> Self {
+        Self {
+            offset: UtcOffset::current_local_offset().unwrap_or(UtcOffset::UTC),
+        }
+    }
+}
+
+impl Collector for FacetCounter {
+    type Fruit = Facets;
+
+    type Child = FacetSegmentCounter;
+
+    fn for_segment(
+        &self,
+        _segment_local_id: SegmentOrdinal,
+        segment: &SegmentReader,
+    ) -> tantivy::Result<FacetSegmentCounter> {
+        let fast_fields = segment.fast_fields();
+        Ok(FacetSegmentCounter {
+            offset: self.offset,
+            class: fast_fields.str("class")?,
+            folder: fast_fields.str("folder")?,
+            timestamp: fast_fields.u64("timestamp")?,
+            classes: HashMap::new(),
+            folders: HashMap::new(),
+            years: HashMap::new(),
+        })
+    }
+
+    fn requires_scoring(&self) -> bool {
+        false
+    }
+
+    fn merge_fruits(&self, segment_fruits: Vec<SegmentFacets>) -> tantivy::Result<Facets> {
+        let mut merged = SegmentFacets::default();
+        for fruit in segment_fruits {
+            for (value, count) in fruit.classes {
+                *merged.classes.entry(value).or_default() += count;
+            }
+            for (value, count) in fruit.folders {
+                *merged.folders.entry(value).or_default() += count;
+            }
+            for (value, count) in fruit.years {
+                *merged.years.entry(value).or_default() += count;
+            }
+        }
+
+        let mut years = to_counts(merged.years);
+        years.sort_by(|a, b| b.value.cmp(&a.value));
+        Ok(Facets {
+            classes: to_counts(merged.classes),
+            folders: to_counts(merged.folders),
+            years,
+        })
+    }
+}
+
+pub struct FacetSegmentCounter {
+    offset: UtcOffset,
+    class: Option<StrColumn>,
+    folder: Option<StrColumn>,
+    timestamp: Column<u64>,
+    classes: HashMap<u64, usize>,
+    folders: HashMap<u64, usize>,
+    years: HashMap<i32, usize>,
+}
+
+impl SegmentCollector for FacetSegmentCounter {
+    type Fruit = SegmentFacets;
+
+    fn collect(&mut self, doc: DocId, _score: Score) {
+        if let Some(class) = &self.class {
+            for ord in class.term_ords(doc) {
+                *self.classes.entry(ord).or_default() += 1;
+            }
+        }
+        if let Some(folder) = &self.folder {
+            for ord in folder.term_ords(doc) {
+                *self.folders.entry(ord).or_default() += 1;
+            }
+        }
+        if let Some(timestamp) = self.timestamp.first(doc) {
+            if let Ok(time) = OffsetDateTime::from_unix_timestamp(timestamp as i64) {
+                *self
+                    .years
+                    .entry(time.to_offset(self.offset).year())
+                    .or_default() += 1;
+            }
+        }
+    }
+
+    fn harvest(self) -> SegmentFacets {
+        SegmentFacets {
+            classes: ords_to_str(self.class.as_ref(), self.classes),
+            folders: ords_to_str(self.folder.as_ref(), self.folders),
+            years: self
+                .years
+                .into_iter()
+                .map(|(year, count)| (year.to_string(), count))
+                .collect(),
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct SegmentFacets {
+    classes: HashMap<String, usize>,
+    folders: HashMap<String, usize>,
+    years: HashMap<String, usize>,
+}
+
+/// 词序号只在段内有效，需在段内转换成字符串后再合并
+fn ords_to_str(column: Option<&StrColumn>, counts: HashMap<u64, usize>) -> HashMap<String, usize> {
+    let mut result = HashMap::new();
+    let Some(column) = column else {
+        return result;
+    };
+    for (ord, count) in counts {
+        let mut value = String::new();
+        match column.ord_to_str(ord, &mut value) {
+            Ok(true) => *result.entry(value).or_default() += count,
+            Ok(false) => {}
+            Err(e) => error!("{e}"),
+        }
+    }
+    result
+}
+
+/// 按数量倒序
+fn to_counts(counts: HashMap<String, usize>) -> Vec<FacetCount> {
+    let mut counts: Vec<FacetCount> = counts
+        .into_iter()
+        .map(|(value, count)| FacetCount { value, count })
+        .collect();
+    counts.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.value.cmp(&b.value)));
+    counts
+}
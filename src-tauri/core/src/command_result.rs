@@ -0,0 +1,112 @@
+use std::collections::BTreeMap;
+use std::fmt::{self, Display, Formatter};
+
+use serde::Serialize;
+use tracing::error;
+
+#[allow(dead_code)]
+pub type Result<T> = core::result::Result<T, CommandError>;
+
+/// 返回给前端及其他调用方的错误
+#[derive(Debug, Clone, Serialize)]
+pub struct CommandError {
+    /// 稳定的错误码，格式为“模块名.错误名”，如 search.DocumentNotFound，可据此区分错误或翻译
+    /// 由错误枚举的 #[command_error(code = "...")] 显式指定，重命名模块或变体不会改变
+    pub code: String,
+    /// 中文错误信息
+    pub message: String,
+    /// 错误相关的字段，如 path、id
+    pub details: BTreeMap<String, String>,
+    /// 底层错误，由外到内
+    pub sources: Vec<String>,
+}
+
+impl CommandError {
+    pub fn new(code: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            code: code.into(),
+            message: message.into(),
+            details: BTreeMap::new(),
+            sources: Vec::new(),
+        }
+    }
+
+    pub fn with_detail(mut self, key: impl Into<String>, value: impl Display) -> Self {
+        self.details.insert(key.into(), value.to_string());
+        self
+    }
+
+    /// 由 CommandError 派生宏调用，code 为变体上指定的错误码
+    pub fn from_error(
+        code: &str,
+        details: Vec<(String, String)>,
+        e: &dyn std::error::Error,
+    ) -> Self {
+        let mut sources = Vec::new();
+        let mut source = e.source();
+        while let Some(s) = source {
+            sources.push(s.to_string());
+            source = s.source();
+        }
+        Self {
+            code: code.to_string(),
+            message: e.to_string(),
+            details: details.into_iter().collect(),
+            sources,
+        }
+    }
+}
+
+impl Display for CommandError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)?;
+        for source in &self.sources {
+            write!(f, "\n{source}")?;
+        }
+        Ok(())
+    }
+}
+
+impl From<tokio::task::JoinError> for CommandError {
+    fn from(e: tokio::task::JoinError) -> Self {
+        error!("{e}");
+        Self::from_error("task.Join", Vec::new(), &e)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn serialized_shape() {
+        let e = crate::config::Error::ReadConfigFile {
+            source: std::io::Error::new(std::io::ErrorKind::NotFound, "missing"),
+            path: "word-index.db".to_string(),
+        };
+        let value = serde_json::to_value(CommandError::from(e)).unwrap();
+        assert_eq!(
+            value,
+            json!({
+                "code": "config.ReadConfigFile",
+                "message": "无法读取配置文件：word-index.db",
+                "details": { "path": "word-index.db" },
+                "sources": ["missing"],
+            })
+        );
+    }
+
+    #[test]
+    fn codes_are_explicit() {
+        let e = crate::search::Error::DocumentNotFound {
+            id: "1".to_string(),
+        };
+        assert_eq!(CommandError::from(e).code, "search.DocumentNotFound");
+        let e = CommandError::from(crate::search::Error::InvalidLimit);
+        assert_eq!(e.code, "search.InvalidLimit");
+        assert!(e.details.is_empty());
+        assert!(e.sources.is_empty());
+    }
+}
@@ -0,0 +1,231 @@
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+use serde::{Deserialize, Serialize};
+use snafu::prelude::*;
+use tokio::{fs::File, io::AsyncReadExt, sync::Mutex};
+use word_index_macros::CommandError;
+
+use crate::structs::{SavedQuery, SavedSearch};
+
+const DB: &str = "word-index.db";
+const DB_TMP: &str = "word-index.db.tmp";
+
+/// 最多保留的检索历史条数
+const HISTORY_LIMIT: usize = 20;
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+#[serde(default)]
+pub struct Config {
+    #[serde(default)]
+    pub paths: Vec<String>,
+    /// 简繁统一，修改后需重建索引
+    #[serde(default)]
+    pub chinese_variant: ChineseVariant,
+    /// 结巴自定义词典文件路径，修改后需重建索引
+    #[serde(default)]
+    pub user_dict: Option<String>,
+    /// 停用词文件路径，每行一个词，修改后需重建索引
+    #[serde(default)]
+    pub stop_words: Option<String>,
+    /// 同义词，每项为一组以逗号分隔的同义词，如：DB, 数据库, database
+    #[serde(default)]
+    pub synonyms: Vec<String>,
+    #[serde(default)]
+    pub ranking: Ranking,
+    /// 最近的检索，最新的在前
+    #[serde(default)]
+    pub history: Vec<SavedQuery>,
+    #[serde(default)]
+    pub saved_searches: Vec<SavedSearch>,
+    #[serde(default)]
+    pub server: ServerConfig,
+}
+
+/// 本地 HTTP 接口，只监听 127.0.0.1
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct ServerConfig {
+    /// 桌面版启动时是否同时启动 HTTP 接口
+    pub enabled: bool,
+    pub port: u16,
+    /// 网页版目录（前端构建产物），设置后同时提供只读的网页版检索页面
+    pub web_root: Option<String>,
+    /// 访问令牌，为空时无需令牌即可访问全部根目录
+    pub tokens: Vec<ApiToken>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ApiToken {
+    /// 令牌持有者，用于日志
+    pub name: String,
+    pub token: String,
+    /// 可访问的根目录，为 None 时可访问全部根目录并可创建索引
+    #[serde(default)]
+    pub roots: Option<Vec<String>>,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            port: 8787,
+            web_root: None,
+            tokens: Vec::new(),
+        }
+    }
+}
+
+/// 相关度排序的调整
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct Ranking {
+    /// 各字段的权重，未配置的字段权重为 1
+    pub field_boosts: HashMap<String, f32>,
+    /// 新近度加权的半衰期（天），文档修改时间每早一个半衰期，加权减半；为 0 时不加权
+    pub recency_half_life_days: f64,
+    /// 新近度加权的最大比例，如 1 表示最新的文档评分最多翻倍
+    pub recency_weight: f64,
+}
+
+impl Default for Ranking {
+    fn default() -> Self {
+        Self {
+            field_boosts: HashMap::from([
+                ("name".to_string(), 3.0),
+                ("pinyin".to_string(), 2.0),
+                ("content".to_string(), 1.0),
+            ]),
+            recency_half_life_days: 0.0,
+            recency_weight: 1.0,
+        }
+    }
+}
+
+impl Ranking {
+    pub fn field_boost(&self, name: &str) -> f32 {
+        self.field_boosts.get(name).copied().unwrap_or(1.0)
+    }
+
+    /// 半衰期（秒）及加权比例，未启用时为 None
+    pub fn recency(&self) -> Option<(f64, f64)> {
+        if self.recency_half_life_days > 0.0 && self.recency_weight > 0.0 {
+            Some((self.recency_half_life_days * 86400.0, self.recency_weight))
+        } else {
+            None
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum ChineseVariant {
+    /// 不转换
+    #[default]
+    None,
+    /// 统一为简体
+    Simplified,
+    /// 统一为繁体
+    Traditional,
+}
+
+impl Config {
+    pub async fn load() -> Result<Self> {
+        if let Ok(mut file) = File::open(DB).await {
+            let mut buf = Vec::new();
+            file.read_to_end(&mut buf)
+                .await
+                .context(ReadConfigFile { path: DB })?;
+            serde_json::from_slice(&buf).context(DecodeConfigFile { path: DB })
+        } else {
+            Ok(Self::default())
+        }
+    }
+
+    /// 解析同义词配置，忽略少于两个词的组
+    pub fn synonym_groups(&self) -> Vec<Vec<String>> {
+        self.synonyms
+            .iter()
+            .map(|line| {
+                line.split([',', '，'])
+                    .map(str::trim)
+                    .filter(|s| !s.is_empty())
+                    .map(String::from)
+                    .collect::<Vec<_>>()
+            })
+            .filter(|group| group.len() > 1)
+            .collect()
+    }
+
+    /// 记录检索历史，相同的检索只保留最近一次
+    pub fn push_history(&mut self, query: SavedQuery) {
+        self.history.retain(|q| *q != query);
+        self.history.insert(0, query);
+        self.history.truncate(HISTORY_LIMIT);
+    }
+
+    /// 保存检索，同名的检索将被覆盖
+    pub fn save_search(&mut self, search: SavedSearch) {
+        match self
+            .saved_searches
+            .iter_mut()
+            .find(|s| s.name == search.name)
+        {
+            Some(saved) => *saved = search,
+            None => self.saved_searches.push(search),
+        }
+    }
+
+    pub fn delete_saved_search(&mut self, name: &str) {
+        self.saved_searches.retain(|s| s.name != name);
+    }
+
+    /// 读取配置、修改后保存；同一进程内的修改依次进行，避免并发修改时相互覆盖
+    pub async fn update<T>(f: impl FnOnce(&mut Self) -> T) -> Result<T> {
+        static LOCK: OnceLock<Mutex<()>> = OnceLock::new();
+        let _guard = LOCK.get_or_init(|| Mutex::new(())).lock().await;
+        let mut config = Self::load().await?;
+        let result = f(&mut config);
+        config.save().await?;
+        Ok(result)
+    }
+
+    /// 先写入临时文件再替换配置文件，读取时不会读到写了一半的配置
+    async fn save(&self) -> Result<()> {
+        let json = serde_json::to_string(self).context(EncodeConfig)?;
+        tokio::fs::write(DB_TMP, json)
+            .await
+            .context(SaveConfigFile { path: DB_TMP })?;
+        tokio::fs::rename(DB_TMP, DB)
+            .await
+            .context(SaveConfigFile { path: DB })?;
+        Ok(())
+    }
+}
+
+type Result<T> = core::result::Result<T, Error>;
+
+#[derive(Debug, Snafu, CommandError)]
+pub enum Error {
+    #[snafu(display("无法解编配置文件：{path}"), context(suffix(false)))]
+    #[command_error(code = "config.DecodeConfigFile")]
+    DecodeConfigFile {
+        source: serde_json::error::Error,
+        path: String,
+    },
+    #[snafu(display("无法编码配置"), context(suffix(false)))]
+    #[command_error(code = "config.EncodeConfig")]
+    EncodeConfig { source: serde_json::error::Error },
+    #[snafu(display("无法读取配置文件：{path}"), context(suffix(false)))]
+    #[command_error(code = "config.ReadConfigFile")]
+    ReadConfigFile {
+        source: std::io::Error,
+        path: String,
+    },
+    #[snafu(display("无法保存配置文件：{path}"), context(suffix(false)))]
+    #[command_error(code = "config.SaveConfigFile")]
+    SaveConfigFile {
+        source: std::io::Error,
+        path: String,
+    },
+}
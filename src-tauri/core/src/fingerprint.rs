@@ -0,0 +1,246 @@
+use std::collections::HashMap;
+
+use tantivy::tokenizer::{TextAnalyzer, TokenStream};
+
+use crate::structs::DuplicateKind;
+
+/// 汉明距离不超过该值的两个 simhash 视为近似重复
+pub const NEAR_DISTANCE: u32 = 3;
+
+/// 分段数需大于 NEAR_DISTANCE，近似重复的两个指纹至少有一段完全相同
+const BANDS: u32 = 4;
+
+/// 内容的 md5，内容为空时返回空串
+pub fn content_hash(content: &str) -> String {
+    if content.is_empty() {
+        String::new()
+    } else {
+        format!("{:x}", md5::compute(content.as_bytes()))
+    }
+}
+
+/// 根据分词结果计算 64 位 simhash，内容为空时返回 0
+pub fn simhash(analyzer: &mut TextAnalyzer, content: &str) -> u64 {
+    let mut weights = [0i64; 64];
+    let mut stream = analyzer.token_stream(content);
+    while stream.advance() {
+        let text = &stream.token().text;
+        if text.trim().is_empty() {
+            continue;
+        }
+        let digest = md5::compute(text.as_bytes());
+        let hash = u64::from_be_bytes(digest.0[..8].try_into().unwrap());
+        for (i, weight) in weights.iter_mut().enumerate() {
+            if hash & (1 << i) != 0 {
+                *weight += 1;
+            } else {
+                *weight -= 1;
+            }
+        }
+    }
+
+    weights
+        .iter()
+        .enumerate()
+        .filter(|(_, weight)| **weight > 0)
+        .fold(0, |hash, (i, _)| hash | (1 << i))
+}
+
+/// 将 simhash 相近的项聚类，返回每一类中项的下标，只返回多于一项的类
+pub fn near_duplicate_groups(simhashes: &[u64]) -> Vec<Vec<usize>> {
+    let mut parents: Vec<usize> = (0..simhashes.len()).collect();
+    let bits = 64 / BANDS;
+    for band in 0..BANDS {
+        let mut buckets: HashMap<u64, Vec<usize>> = HashMap::new();
+        for (i, simhash) in simhashes.iter().enumerate() {
+            if *simhash != 0 {
+                let key = (simhash >> (band * bits)) & ((1 << bits) - 1);
+                buckets.entry(key).or_default().push(i);
+            }
+        }
+        for bucket in buckets.values() {
+            for (n, &a) in bucket.iter().enumerate() {
+                for &b in &bucket[n + 1..] {
+                    if (simhashes[a] ^ simhashes[b]).count_ones() <= NEAR_DISTANCE {
+                        union(&mut parents, a, b);
+                    }
+                }
+            }
+        }
+    }
+
+    let mut groups: HashMap<usize, Vec<usize>> = HashMap::new();
+    for i in 0..simhashes.len() {
+        let root = find(&mut parents, i);
+        groups.entry(root).or_default().push(i);
+    }
+    groups.into_values().filter(|g| g.len() > 1).collect()
+}
+
+/// 按内容 md5 及 simhash 将文档聚类，返回每一类的类型及其中文档的下标
+/// 内容完全相同的文档归为一类；与之近似的其他文档并入该类，此时该类为近似重复
+pub fn duplicate_clusters(
+    hashes: &[Option<String>],
+    simhashes: &[u64],
+) -> Vec<(DuplicateKind, Vec<usize>)> {
+    let mut exact: HashMap<&str, Vec<usize>> = HashMap::new();
+    for (i, hash) in hashes.iter().enumerate() {
+        if let Some(hash) = hash {
+            exact.entry(hash).or_default().push(i);
+        }
+    }
+    let mut clusters: Vec<(DuplicateKind, Vec<usize>)> = exact
+        .into_values()
+        .filter(|group| group.len() > 1)
+        .map(|group| (DuplicateKind::Exact, group))
+        .collect();
+    let mut cluster_of = vec![None; simhashes.len()];
+    for (c, (_, group)) in clusters.iter().enumerate() {
+        group.iter().for_each(|&i| cluster_of[i] = Some(c));
+    }
+
+    for group in near_duplicate_groups(simhashes) {
+        let mut exact_clusters: Vec<usize> = group.iter().filter_map(|&i| cluster_of[i]).collect();
+        exact_clusters.sort_unstable();
+        exact_clusters.dedup();
+        let mut members: Vec<usize> = group
+            .into_iter()
+            .filter(|&i| cluster_of[i].is_none())
+            .collect();
+        // 全部属于同一个内容相同的类时，已作为完全相同的类返回
+        if members.is_empty() && exact_clusters.len() <= 1 {
+            continue;
+        }
+        for c in exact_clusters {
+            members.append(&mut clusters[c].1);
+        }
+        members.sort_unstable();
+        clusters.push((DuplicateKind::Near, members));
+    }
+    clusters.retain(|(_, group)| group.len() > 1);
+    clusters
+}
+
+fn find(parents: &mut [usize], i: usize) -> usize {
+    let mut root = i;
+    while parents[root] != root {
+        root = parents[root];
+    }
+    let mut i = i;
+    while parents[i] != root {
+        let next = parents[i];
+        parents[i] = root;
+        i = next;
+    }
+    root
+}
+
+fn union(parents: &mut [usize], a: usize, b: usize) {
+    let a = find(parents, a);
+    let b = find(parents, b);
+    if a != b {
+        parents[b] = a;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::tokenizer;
+
+    fn simhash_of(content: &str) -> u64 {
+        let mut analyzer = tokenizer::analyzer(&Config::default()).unwrap();
+        simhash(&mut analyzer, content)
+    }
+
+    fn distance(a: u64, b: u64) -> u32 {
+        (a ^ b).count_ones()
+    }
+
+    const TEXT: &str = "今天上午，项目组在三楼会议室召开了季度总结会议，\
+        会上各小组汇报了本季度的工作进展、存在的问题以及下一季度的工作计划，\
+        并对数据库迁移、接口改造和性能优化等重点任务进行了讨论和分工。";
+
+    #[test]
+    fn simhash_of_empty_content_is_zero() {
+        assert_eq!(simhash_of(""), 0);
+        assert_eq!(simhash_of("  \n"), 0);
+    }
+
+    #[test]
+    fn simhash_is_stable() {
+        assert_eq!(simhash_of(TEXT), simhash_of(TEXT));
+        assert_ne!(simhash_of(TEXT), 0);
+    }
+
+    #[test]
+    fn simhash_of_similar_content_is_close() {
+        // 较长的文档中改动一处，仍视为近似重复
+        let content = TEXT.repeat(5);
+        let edited = content.replacen("三楼", "四楼", 1);
+        assert!(distance(simhash_of(&content), simhash_of(&edited)) <= NEAR_DISTANCE);
+
+        let other = "Rust 的所有权系统在编译期保证内存安全，无需垃圾回收，\
+            借用检查器会拒绝悬垂引用和数据竞争。";
+        assert!(distance(simhash_of(&content), simhash_of(other)) > NEAR_DISTANCE);
+    }
+
+    #[test]
+    fn near_duplicate_groups_by_distance() {
+        let a = 0x0123_4567_89ab_cdef;
+        let groups = near_duplicate_groups(&[a, a ^ 0b111, !a, 0, 0, a ^ 0xf0f0]);
+        assert_eq!(groups.len(), 1);
+        let mut group = groups[0].clone();
+        group.sort_unstable();
+        assert_eq!(group, [0, 1]);
+    }
+
+    #[test]
+    fn near_duplicate_groups_are_transitive() {
+        let a: u64 = 0xffff_0000_ffff_0000;
+        let b = a ^ 0b11;
+        let c = b ^ 0b1100;
+        let groups = near_duplicate_groups(&[a, b, c]);
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0].len(), 3);
+    }
+
+    fn sorted(mut clusters: Vec<(DuplicateKind, Vec<usize>)>) -> Vec<(DuplicateKind, Vec<usize>)> {
+        for (_, group) in &mut clusters {
+            group.sort_unstable();
+        }
+        clusters.sort_by(|a, b| a.1.cmp(&b.1));
+        clusters
+    }
+
+    #[test]
+    fn near_duplicate_joins_exact_cluster() {
+        let a: u64 = 0x0f0f_0f0f_0f0f_0f0f;
+        let hash = Some("md5".to_string());
+        let hashes = [hash.clone(), hash, Some("other".to_string())];
+        let clusters = duplicate_clusters(&hashes, &[a, a, a ^ 1]);
+        assert_eq!(sorted(clusters), [(DuplicateKind::Near, vec![0, 1, 2])]);
+    }
+
+    #[test]
+    fn exact_cluster_is_not_reported_twice() {
+        let a: u64 = 0x0f0f_0f0f_0f0f_0f0f;
+        let b = !a;
+        let hash = Some("md5".to_string());
+        let hashes = [
+            hash.clone(),
+            hash,
+            Some("x".to_string()),
+            Some("y".to_string()),
+        ];
+        let clusters = duplicate_clusters(&hashes, &[a, a, b, b ^ 1]);
+        assert_eq!(
+            sorted(clusters),
+            [
+                (DuplicateKind::Exact, vec![0, 1]),
+                (DuplicateKind::Near, vec![2, 3]),
+            ]
+        );
+    }
+}
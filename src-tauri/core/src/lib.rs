@@ -0,0 +1,18 @@
+//! word-index 的索引与检索核心，不依赖 Tauri，桌面版、命令行版等均基于此构建
+
+// CommandError 派生宏生成的代码以 ::word_index_core 引用 CommandError，在本 crate 内同样适用
+extern crate self as word_index_core;
+
+pub mod collector;
+pub mod command_result;
+pub mod config;
+pub mod fingerprint;
+pub mod search;
+pub mod structs;
+pub mod tokenizer;
+
+pub use command_result::{CommandError, Result};
+pub use config::Config;
+pub use search::SearchState;
+pub use structs::{extract_text, Docx};
+pub use tantivy;
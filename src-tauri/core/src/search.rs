@@ -0,0 +1,1397 @@
+use std::{
+    cmp::Reverse,
+    collections::{HashMap, HashSet, VecDeque},
+    fs::{create_dir, remove_dir_all},
+    ops::Bound,
+    path::{Component, Path},
+    sync::{Arc, Mutex},
+    time::SystemTime,
+};
+
+use async_walkdir::{Filtering, WalkDir};
+use snafu::ensure;
+use snafu::OptionExt;
+use snafu::ResultExt;
+use snafu::Snafu;
+use tantivy::tokenizer::TextAnalyzer;
+use tantivy::{
+    collector::{Collector, Count, DocSetCollector, MultiCollector, TopDocs},
+    directory::MmapDirectory,
+    query::{
+        AllQuery, BooleanQuery, BoostQuery, Explanation, MoreLikeThisQuery, Occur, Query,
+        QueryParser, QueryParserError, RangeQuery, RegexQuery, TermQuery, TermSetQuery,
+    },
+    schema::{Field, IndexRecordOption, Schema},
+    tokenizer::TokenizerManager,
+    DocAddress, DocId, Document, Index, IndexBuilder, IndexReader, IndexSettings, IndexSortByField,
+    IndexWriter, Order, Score, Searcher, SegmentReader, TantivyError, Term, UserOperation,
+};
+use tokio_stream::StreamExt;
+use tracing::{error, warn};
+
+use crate::collector::FacetCounter;
+use crate::config::{Config, Ranking};
+use crate::fingerprint;
+use crate::structs::{
+    Docx, DuplicateCluster, Expansion, Facets, IndexStats, SavedSearch, SearchAlert, SearchFruit,
+    SearchHit, SearchMode, SearchOptions, SortBy, SortOrder, StartupStatus, Suggestion,
+};
+use crate::tokenizer;
+use word_index_macros::CommandError;
+
+const BATCH_NUM: u8 = 100;
+
+/// 命中的文档及与其内容相同的文档
+type Matched = (DocAddress, Vec<DocAddress>);
+
+/// 每个保存的检索最多提醒的新文档数
+const ALERT_LIMIT: usize = 10;
+
+/// 精确子串模式缓存结果的关键字数
+const SUBSTRING_CACHE_SIZE: usize = 8;
+
+/// 精确子串模式下文件名或内容包含关键字的全部文档
+/// 查找需读取并遍历全部文档的内容，耗时与索引大小成正比；
+/// 缓存后同一关键字翻页、修改过滤条件时无需再次读取，索引变化（searcher 换代）后失效
+struct SubstringMatches {
+    generation: u64,
+    keyword: String,
+    docs: Arc<HashSet<DocAddress>>,
+}
+
+/// 保存的检索有新文档时的回调
+pub type AlertHandler = Arc<dyn Fn(Vec<SearchAlert>) + Send + Sync>;
+
+#[derive(Clone)]
+pub struct SearchState {
+    pub schema: Schema,
+    pub index: Index,
+    pub reader: IndexReader,
+    pub parser: QueryParser,
+    pub startup: StartupStatus,
+    alert_handler: Option<AlertHandler>,
+    substring_cache: Arc<Mutex<VecDeque<SubstringMatches>>>,
+}
+
+impl SearchState {
+    pub fn new(config: &Config) -> Self {
+        let mut startup = StartupStatus::default();
+        // 用户词典或停用词无法读取时退回内置词典，避免无法启动
+        let tokenizer = tokenizer::analyzer(config).unwrap_or_else(|e| {
+            error!("{e}");
+            startup.tokenizer_error = Some(e.into());
+            tokenizer::builtin_analyzer(config)
+        });
+        let data_path = Path::new("./data");
+        if !data_path.exists() || !data_path.is_dir() {
+            create_dir(data_path).unwrap();
+        }
+        let builder = || Self::builder(tokenizer.clone());
+        let dir = MmapDirectory::open(data_path).unwrap();
+        let index = match builder().open_or_create(dir) {
+            // 索引结构变更后，旧索引无法打开，需要清空后重新创建索引
+            Err(TantivyError::SchemaError(e)) => {
+                warn!("重建索引：{e}");
+                startup.rebuilt = true;
+                remove_dir_all(data_path).unwrap();
+                create_dir(data_path).unwrap();
+                builder().create_in_dir(data_path)
+            }
+            result => result,
+        }
+        .expect("创建索引失败");
+        Self::with_index(index, startup)
+    }
+
+    /// 索引结构、分词器及按修改时间排序的设置
+    fn builder(tokenizer: TextAnalyzer) -> IndexBuilder {
+        let tokenizers = TokenizerManager::default();
+        tokenizers.register("default", tokenizer);
+        let settings = IndexSettings {
+            sort_by_field: Some(IndexSortByField {
+                field: "timestamp".to_string(),
+                order: Order::Desc,
+            }),
+            ..Default::default()
+        };
+        Index::builder()
+            .schema(Docx::schema())
+            .tokenizers(tokenizers)
+            .settings(settings)
+    }
+
+    fn with_index(index: Index, startup: StartupStatus) -> Self {
+        let schema = index.schema();
+        let reader = index.reader().expect("创建Reader失败");
+        let default_fields = ["name", "content", "pinyin"]
+            .iter()
+            .filter_map(|name| schema.get_field(name).ok())
+            .collect();
+        let parser = QueryParser::for_index(&index, default_fields);
+        Self {
+            schema,
+            index,
+            reader,
+            parser,
+            startup,
+            alert_handler: None,
+            substring_cache: Arc::default(),
+        }
+    }
+
+    /// 设置保存的检索有新文档时的回调，每次索引提交后检查；未设置时不检查
+    pub fn on_alerts(mut self, handler: impl Fn(Vec<SearchAlert>) + Send + Sync + 'static) -> Self {
+        self.alert_handler = Some(Arc::new(handler));
+        self
+    }
+
+    /// 为目录下的文件创建索引，返回本次新增或更新的文档数
+    pub async fn index(&self, dir_path: String) -> Result<usize> {
+        let started = now();
+        let mut writer = self.index.writer(100_000_000).context(CreateWriter)?;
+        let searcher = self.reader.searcher();
+        let mut analyzer = self
+            .index
+            .tokenizers()
+            .get("default")
+            .context(TokenizerNotFound { name: "default" })?;
+
+        let mut entries = WalkDir::new(&dir_path).filter(|entry| async move {
+            if let Some(true) = entry
+                .path()
+                .file_name()
+                .map(|f| f.to_string_lossy().starts_with('.'))
+            {
+                return Filtering::IgnoreDir;
+            }
+            Filtering::Continue
+        });
+
+        let mut i = 0;
+        let mut added = 0;
+        loop {
+            match entries.next().await {
+                Some(Ok(entry)) => {
+                    if let Ok(mut docx) = Docx::new(&entry).await.inspect_err(|e| error!("{e}")) {
+                        docx.set_folder(&dir_path);
+                        if Self::exists(&searcher, &self.parser, &docx) {
+                            continue;
+                        }
+                        if Self::add_document(&mut writer, &mut analyzer, docx)
+                            .await
+                            .inspect_err(|e| error!("{e}"))
+                            .is_ok()
+                        {
+                            added += 1;
+                        }
+                    }
+                }
+                Some(e) => {
+                    e.context(ReadDir)?;
+                }
+                None => break,
+            }
+            i += 1;
+            if i % BATCH_NUM == 0 {
+                writer.commit().context(Commit)?;
+            }
+        }
+
+        writer.commit().context(Commit)?;
+        // 提交后立即可检索到新文档，便于检查保存的检索
+        self.reader.reload().context(ReloadReader)?;
+        self.notify_alerts(started).await;
+
+        Ok(added)
+    }
+
+    /// 检查保存的检索在上次检查后是否有新文档，并将检查时间更新为 started
+    /// 新文档按文件修改时间判断，started 应为本次索引开始的时间，而不是检查的时间：
+    /// 索引期间修改的文件可能要到下次索引才被索引，检查时间更新为当前时间会漏掉这些文件
+    pub async fn check_saved_searches(&self, started: u64) -> Result<Vec<SearchAlert>> {
+        let config = Config::load().await.context(LoadConfig)?;
+        let mut alerts = Vec::new();
+        for saved in &config.saved_searches {
+            let alert = self.new_matches(saved, &config)?;
+            if alert.total > 0 {
+                alerts.push(alert);
+            }
+        }
+        if config.saved_searches.is_empty() {
+            return Ok(alerts);
+        }
+
+        // 检查期间新保存的检索，其 last_checked 为保存时间，不应覆盖
+        let checked: Vec<String> = config.saved_searches.into_iter().map(|s| s.name).collect();
+        Config::update(|config| {
+            for saved in &mut config.saved_searches {
+                if checked.contains(&saved.name) {
+                    saved.last_checked = saved.last_checked.max(started);
+                }
+            }
+        })
+        .await
+        .context(LoadConfig)?;
+        Ok(alerts)
+    }
+
+    /// 索引提交后检查保存的检索；检查失败（如保存的正则无效）只记录日志，不影响索引结果
+    async fn notify_alerts(&self, started: u64) {
+        let Some(handler) = &self.alert_handler else {
+            return;
+        };
+        match self.check_saved_searches(started).await {
+            Ok(alerts) if !alerts.is_empty() => handler(alerts),
+            Ok(_) => {}
+            Err(e) => error!("{e}"),
+        }
+    }
+
+    /// 重新加载分词器（自定义词典、停用词等），清空并重建所有路径的索引
+    pub async fn reindex(&self, config: &Config) -> Result<()> {
+        let tokenizer = tokenizer::analyzer(config).context(CreateTokenizer)?;
+        self.index.tokenizers().register("default", tokenizer);
+
+        let mut writer: IndexWriter = self.index.writer(100_000_000).context(CreateWriter)?;
+        writer.delete_all_documents().context(DeleteDocument)?;
+        writer.commit().context(Commit)?;
+        drop(writer);
+        // 确保判断文档是否存在时，不再读取到已删除的文档
+        self.reader.reload().context(ReloadReader)?;
+
+        for path in &config.paths {
+            self.index(path.clone()).await?;
+        }
+        Ok(())
+    }
+
+    /// 清理索引：all 为 true 时清空索引，否则只删除文件已不存在的文档，返回删除的文档数
+    pub fn purge(&self, all: bool) -> Result<u64> {
+        let searcher = self.reader.searcher();
+        let mut writer: IndexWriter = self.index.writer(100_000_000).context(CreateWriter)?;
+        let removed = if all {
+            writer.delete_all_documents().context(DeleteDocument)?;
+            searcher.num_docs()
+        } else {
+            let id_field = self.field("id")?;
+            let docs = searcher
+                .search(&AllQuery, &DocSetCollector)
+                .context(SearchDocument)?;
+            let mut removed = 0;
+            for doc_address in docs {
+                let retrieved_doc = searcher.doc(doc_address).context(SearchDocument)?;
+                let path = Self::get_field_value(&retrieved_doc, &self.schema, "path");
+                if !Path::new(&path).exists() {
+                    let id = Self::get_field_value(&retrieved_doc, &self.schema, "id");
+                    writer.delete_term(Term::from_field_text(id_field, &id));
+                    removed += 1;
+                }
+            }
+            removed
+        };
+        writer.commit().context(Commit)?;
+        self.reader.reload().context(ReloadReader)?;
+        Ok(removed)
+    }
+
+    /// 索引统计：文档数、段数，以及各类型、各顶层目录的文档数
+    pub fn stats(&self) -> Result<IndexStats> {
+        let searcher = self.reader.searcher();
+        let facets = searcher
+            .search(&AllQuery, &FacetCounter::default())
+            .context(SearchDocument)?;
+        Ok(IndexStats {
+            docs: searcher.num_docs(),
+            segments: searcher.segment_readers().len(),
+            classes: facets.classes,
+            folders: facets.folders,
+        })
+    }
+
+    pub fn search(
+        &self,
+        keyword: String,
+        offset: usize,
+        limit: usize,
+        classes: Option<Vec<String>>,
+        options: &SearchOptions,
+        config: &Config,
+    ) -> Result<SearchFruit> {
+        ensure!(limit > 0, InvalidLimit);
+        let ranking = &config.ranking;
+        let (query, expansions) = self.query(&keyword, classes.as_deref(), options, config)?;
+
+        let searcher = self.reader.searcher();
+        let (docs, total, facets) = if options.mode == SearchMode::Substring
+            || options.collapse
+            || options.sort_by == SortBy::Name
+        {
+            let (matched, facets) =
+                self.collect_filtered(&searcher, &query, &keyword, options, ranking)?;
+            let total = matched.len();
+            let page = matched.into_iter().skip(offset).take(limit);
+            (self.load_hits(&searcher, page)?, total, facets)
+        } else {
+            self.collect(&searcher, &query, offset, limit, options, ranking)?
+        };
+        Ok(SearchFruit {
+            results: docs,
+            total,
+            limit,
+            offset,
+            expansions,
+            facets,
+        })
+    }
+
+    /// 根据 id 读取文档，文档不存在或不在 roots 下时返回 None
+    pub fn get_document(&self, id: &str, roots: Option<&[String]>) -> Result<Option<Docx>> {
+        let searcher = self.reader.searcher();
+        let Some(doc_address) = self.find_doc(&searcher, id, roots)? else {
+            return Ok(None);
+        };
+        let retrieved_doc = searcher.doc(doc_address).context(SearchDocument)?;
+        Ok(Some(self.to_docx(&retrieved_doc)))
+    }
+
+    /// 检索保存的检索在上次检查后新出现的文档，按修改时间判断，最新的在前
+    pub fn new_matches(&self, saved: &SavedSearch, config: &Config) -> Result<SearchAlert> {
+        let query = &saved.query;
+        let mut options = query.options.clone();
+        let since = saved.last_checked + 1;
+        options.modified_after = Some(options.modified_after.map_or(since, |t| t.max(since)));
+        options.sort_by = SortBy::Timestamp;
+        options.order = SortOrder::Desc;
+        let fruit = self.search(
+            query.keyword.clone(),
+            0,
+            ALERT_LIMIT,
+            query.classes.clone(),
+            &options,
+            config,
+        )?;
+        Ok(SearchAlert {
+            name: saved.name.clone(),
+            total: fruit.total,
+            results: fruit.results,
+        })
+    }
+
+    /// 解释指定文档在本次检索中的评分，与 search 使用相同的查询
+    pub fn explain(
+        &self,
+        keyword: String,
+        id: &str,
+        classes: Option<Vec<String>>,
+        options: &SearchOptions,
+        config: &Config,
+    ) -> Result<Explanation> {
+        let (query, _expansions) = self.query(&keyword, classes.as_deref(), options, config)?;
+        let searcher = self.reader.searcher();
+        let doc_address = self.doc_address(&searcher, id, options.allowed_roots.as_deref())?;
+        let explanation = query
+            .explain(&searcher, doc_address)
+            .context(ExplainScore { id })?;
+
+        let Some((half_life, weight)) = config.ranking.recency() else {
+            return Ok(explanation);
+        };
+        if options.sort_by != SortBy::Relevance {
+            return Ok(explanation);
+        }
+        let retrieved_doc = searcher.doc(doc_address).context(SearchDocument)?;
+        let timestamp = Self::get_u64_value(&retrieved_doc, &self.schema, "timestamp");
+        let factor = recency_factor(now(), timestamp, half_life, weight);
+        let mut result = Explanation::new("新近度加权", explanation.value() * factor as Score);
+        result.add_detail(explanation);
+        result.add_const("修改时间加权系数", factor as Score);
+        Ok(result)
+    }
+
+    /// 检索与指定文档相似的文档，不包含该文档本身
+    /// roots 不为 None 时，只能检索这些根目录下的文档
+    pub fn similar(
+        &self,
+        id: &str,
+        offset: usize,
+        limit: usize,
+        roots: Option<&[String]>,
+    ) -> Result<SearchFruit> {
+        ensure!(limit > 0, InvalidLimit);
+        let searcher = self.reader.searcher();
+        let id_term = Term::from_field_text(self.field("id")?, id);
+        let doc_address = self.doc_address(&searcher, id, roots)?;
+        let retrieved_doc = searcher.doc(doc_address).context(SearchDocument)?;
+
+        let mut doc_fields = Vec::new();
+        for name in ["name", "content"] {
+            let field = self.field(name)?;
+            doc_fields.push((field, retrieved_doc.get_all(field).cloned().collect()));
+        }
+        let more_like_this = MoreLikeThisQuery::builder()
+            .with_min_doc_frequency(2)
+            .with_min_term_frequency(1)
+            .with_min_word_length(2)
+            .with_document_fields(doc_fields);
+        let mut queries: Vec<(Occur, Box<dyn Query>)> = vec![
+            (Occur::Must, Box::new(more_like_this)),
+            (
+                Occur::MustNot,
+                Box::new(TermQuery::new(id_term, IndexRecordOption::Basic)),
+            ),
+        ];
+        if let Some(roots) = roots {
+            queries.push((Occur::Must, self.folders_query(roots)?));
+        }
+        let query = BooleanQuery::new(queries);
+
+        let options = SearchOptions::default();
+        let ranking = Ranking::default();
+        let (docs, total, facets) =
+            self.collect(&searcher, &query, offset, limit, &options, &ranking)?;
+        Ok(SearchFruit {
+            results: docs,
+            total,
+            limit,
+            offset,
+            expansions: Vec::new(),
+            facets,
+        })
+    }
+
+    /// 收集分页后的文档、命中总数及分布
+    fn collect(
+        &self,
+        searcher: &Searcher,
+        query: &dyn Query,
+        offset: usize,
+        limit: usize,
+        options: &SearchOptions,
+        ranking: &Ranking,
+    ) -> Result<(Vec<SearchHit>, usize, Facets)> {
+        let mut collectors = MultiCollector::new();
+        let top_docs_handle =
+            collectors.add_collector(Self::top_docs(limit, offset, options, ranking));
+        let count_handle = collectors.add_collector(Count);
+        let facets_handle = collectors.add_collector(FacetCounter::default());
+        let mut multi_fruit = searcher
+            .search(query, &collectors)
+            .context(SearchDocument)?;
+        let total = count_handle.extract(&mut multi_fruit);
+        let facets = facets_handle.extract(&mut multi_fruit);
+        let top_docs = top_docs_handle.extract(&mut multi_fruit);
+
+        let mut docs = Vec::new();
+        for (_score, doc_address) in top_docs {
+            // Retrieve the actual content of documents given its `doc_address`.
+            let retrieved_doc = searcher.doc(doc_address).context(SearchDocument)?;
+            docs.push(self.to_docx(&retrieved_doc).into());
+        }
+        Ok((docs, total, facets))
+    }
+
+    /// 需要逐一处理候选文档的检索：按排序取出全部候选文档，按文件名排序时在此排序，
+    /// 精确子串模式下校验文件名或内容中是否包含该子串，合并重复文档时按内容 md5 分组
+    /// 返回命中的文档及与其内容相同的文档
+    fn collect_filtered(
+        &self,
+        searcher: &Searcher,
+        query: &dyn Query,
+        keyword: &str,
+        options: &SearchOptions,
+        ranking: &Ranking,
+    ) -> Result<(Vec<Matched>, Facets)> {
+        let keyword = keyword.to_lowercase();
+        let num_docs = (searcher.num_docs() as usize).max(1);
+        let mut candidates: Vec<DocAddress> = searcher
+            .search(query, &Self::top_docs(num_docs, 0, options, ranking))
+            .context(SearchDocument)?
+            .into_iter()
+            .map(|(_score, doc_address)| doc_address)
+            .collect();
+        if options.sort_by == SortBy::Name {
+            Self::sort_by_name(searcher, &mut candidates, options.order);
+        }
+
+        let substring = if options.mode == SearchMode::Substring {
+            Some(self.substring_matches(searcher, &keyword)?)
+        } else {
+            None
+        };
+
+        let mut matched: Vec<Matched> = Vec::new();
+        let mut groups: HashMap<String, usize> = HashMap::new();
+        for doc_address in candidates {
+            if matches!(&substring, Some(docs) if !docs.contains(&doc_address)) {
+                continue;
+            }
+            if options.collapse {
+                if let Some(hash) = Self::get_fast_str(searcher, doc_address, "hash") {
+                    if let Some(&i) = groups.get(&hash) {
+                        matched[i].1.push(doc_address);
+                        continue;
+                    }
+                    groups.insert(hash, matched.len());
+                }
+            }
+            matched.push((doc_address, Vec::new()));
+        }
+
+        let id = self.field("id")?;
+        let ids = TermSetQuery::new(matched.iter().filter_map(|(doc_address, _)| {
+            Self::get_fast_str(searcher, *doc_address, "id").map(|s| Term::from_field_text(id, &s))
+        }));
+        let facets = searcher
+            .search(&ids, &FacetCounter::default())
+            .context(SearchDocument)?;
+        Ok((matched, facets))
+    }
+
+    /// 文件名或内容包含关键字（小写）的全部文档，优先使用缓存
+    fn substring_matches(
+        &self,
+        searcher: &Searcher,
+        keyword: &str,
+    ) -> Result<Arc<HashSet<DocAddress>>> {
+        let generation = searcher.generation().generation_id();
+        let lock = || {
+            self.substring_cache
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+        };
+        if let Some(cached) = lock()
+            .iter()
+            .find(|m| m.generation == generation && m.keyword == keyword)
+        {
+            return Ok(cached.docs.clone());
+        }
+
+        let mut docs = HashSet::new();
+        for (segment_ord, segment_reader) in searcher.segment_readers().iter().enumerate() {
+            for doc_id in segment_reader.doc_ids_alive() {
+                let doc_address = DocAddress::new(segment_ord as u32, doc_id);
+                let retrieved_doc = searcher.doc(doc_address).context(SearchDocument)?;
+                let name = Self::get_field_value(&retrieved_doc, &self.schema, "name");
+                let content = Self::get_field_value(&retrieved_doc, &self.schema, "content");
+                if name.to_lowercase().contains(keyword) || content.to_lowercase().contains(keyword)
+                {
+                    docs.insert(doc_address);
+                }
+            }
+        }
+        let docs = Arc::new(docs);
+
+        let mut cache = lock();
+        cache.retain(|m| m.generation == generation);
+        cache.push_front(SubstringMatches {
+            generation,
+            keyword: keyword.to_string(),
+            docs: docs.clone(),
+        });
+        cache.truncate(SUBSTRING_CACHE_SIZE);
+        Ok(docs)
+    }
+
+    /// 按小写的完整文件名排序，文件名相同时保持原有顺序
+    fn sort_by_name(searcher: &Searcher, docs: &mut [DocAddress], order: SortOrder) {
+        docs.sort_by_cached_key(|doc_address| {
+            Self::get_fast_str(searcher, *doc_address, "name_key").unwrap_or_default()
+        });
+        if order == SortOrder::Desc {
+            docs.reverse();
+        }
+    }
+
+    fn load_hits(
+        &self,
+        searcher: &Searcher,
+        matched: impl Iterator<Item = Matched>,
+    ) -> Result<Vec<SearchHit>> {
+        let mut hits = Vec::new();
+        for (doc_address, duplicates) in matched {
+            let retrieved_doc = searcher.doc(doc_address).context(SearchDocument)?;
+            let mut hit = SearchHit::from(self.to_docx(&retrieved_doc));
+            for duplicate in duplicates {
+                let retrieved_doc = searcher.doc(duplicate).context(SearchDocument)?;
+                hit.duplicates
+                    .push(Self::get_field_value(&retrieved_doc, &self.schema, "path"));
+            }
+            hits.push(hit);
+        }
+        Ok(hits)
+    }
+
+    /// 列出内容相同或近似的文档
+    pub fn duplicates(&self) -> Result<Vec<DuplicateCluster>> {
+        let searcher = self.reader.searcher();
+        let mut doc_addresses = Vec::new();
+        let mut hashes = Vec::new();
+        let mut simhashes = Vec::new();
+        for (segment_ord, segment_reader) in searcher.segment_readers().iter().enumerate() {
+            let simhash = segment_reader
+                .fast_fields()
+                .u64("simhash")
+                .context(SearchDocument)?;
+            for doc_id in segment_reader.doc_ids_alive() {
+                let doc_address = DocAddress::new(segment_ord as u32, doc_id);
+                hashes.push(Self::get_fast_str(&searcher, doc_address, "hash"));
+                doc_addresses.push(doc_address);
+                simhashes.push(simhash.first(doc_id).unwrap_or_default());
+            }
+        }
+
+        let mut result = Vec::new();
+        for (kind, group) in fingerprint::duplicate_clusters(&hashes, &simhashes) {
+            let mut paths = Vec::new();
+            for i in group {
+                let retrieved_doc = searcher.doc(doc_addresses[i]).context(SearchDocument)?;
+                paths.push(Self::get_field_value(&retrieved_doc, &self.schema, "path"));
+            }
+            paths.sort();
+            result.push(DuplicateCluster { kind, paths });
+        }
+        result.sort_by_key(|cluster| Reverse(cluster.paths.len()));
+        Ok(result)
+    }
+
+    /// 构建检索使用的查询：关键字查询及过滤条件
+    fn query(
+        &self,
+        keyword: &str,
+        classes: Option<&[String]>,
+        options: &SearchOptions,
+        config: &Config,
+    ) -> Result<(Box<dyn Query>, Vec<Expansion>)> {
+        let (query, expansions) = self.keyword_query(keyword, options.mode, config)?;
+        let query = self.with_filters(query, classes.unwrap_or_default(), options)?;
+        Ok((query, expansions))
+    }
+
+    /// 根据文档 id 查找文档地址，无权访问的文档视为不存在
+    fn doc_address(
+        &self,
+        searcher: &Searcher,
+        id: &str,
+        roots: Option<&[String]>,
+    ) -> Result<DocAddress> {
+        self.find_doc(searcher, id, roots)?
+            .context(DocumentNotFound { id })
+    }
+
+    fn find_doc(
+        &self,
+        searcher: &Searcher,
+        id: &str,
+        roots: Option<&[String]>,
+    ) -> Result<Option<DocAddress>> {
+        let id_term = Term::from_field_text(self.field("id")?, id);
+        let source = searcher
+            .search(
+                &TermQuery::new(id_term, IndexRecordOption::Basic),
+                &TopDocs::with_limit(1),
+            )
+            .context(SearchDocument)?;
+        let Some((_score, doc_address)) = source.first() else {
+            return Ok(None);
+        };
+        if let Some(roots) = roots {
+            let retrieved_doc = searcher.doc(*doc_address).context(SearchDocument)?;
+            let path = Self::get_field_value(&retrieved_doc, &self.schema, "path");
+            if !is_under_roots(&path, roots) {
+                return Ok(None);
+            }
+        }
+        Ok(Some(*doc_address))
+    }
+
+    /// 按检索模式构建关键字查询，并按配置调整各字段的权重
+    fn keyword_query(
+        &self,
+        keyword: &str,
+        mode: SearchMode,
+        config: &Config,
+    ) -> Result<(Box<dyn Query>, Vec<Expansion>)> {
+        if keyword.is_empty() {
+            return Ok((Box::new(AllQuery), Vec::new()));
+        }
+        match mode {
+            SearchMode::Keyword => {
+                // 未指定字段的词，将在 name、content、pinyin 中检索
+                let (filter, expansions) = Self::expand_synonyms(keyword, &config.synonym_groups());
+                let mut parser = self.parser.clone();
+                for (name, boost) in &config.ranking.field_boosts {
+                    if let Ok(field) = self.schema.get_field(name) {
+                        parser.set_field_boost(field, *boost);
+                    }
+                }
+                let query = parser.parse_query(&filter).context(SearchParser)?;
+                Ok((query, expansions))
+            }
+            SearchMode::Regex => {
+                // 索引中的词均为小写，故忽略大小写
+                let pattern = format!("(?i){keyword}");
+                let mut queries: Vec<(Occur, Box<dyn Query>)> = Vec::new();
+                for name in ["name", "content"] {
+                    let query = RegexQuery::from_pattern(&pattern, self.field(name)?)
+                        .context(RegexPattern { pattern: keyword })?;
+                    let boost = config.ranking.field_boost(name);
+                    queries.push((
+                        Occur::Should,
+                        Box::new(BoostQuery::new(Box::new(query), boost)),
+                    ));
+                }
+                Ok((Box::new(BooleanQuery::new(queries)), Vec::new()))
+            }
+            // 分词结果与子串的切分不一致（如 t_user_0 与 _user_），按词筛选候选文档会漏掉匹配，
+            // 故候选为全部文档，由 collect_filtered 按 substring_matches 的结果筛选
+            SearchMode::Substring => Ok((Box::new(AllQuery), Vec::new())),
+        }
+    }
+
+    /// 根据前缀，从 content、name 的词典中补全，按文档频率倒序
+    pub fn suggest(
+        &self,
+        prefix: &str,
+        limit: usize,
+        roots: Option<&[String]>,
+    ) -> Result<Vec<Suggestion>> {
+        let prefix = prefix.trim().to_lowercase();
+        if prefix.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let searcher = self.reader.searcher();
+        let mut doc_freqs: HashMap<String, u32> = HashMap::new();
+        for name in ["name", "content"] {
+            let field = self.field(name)?;
+            for segment_reader in searcher.segment_readers() {
+                let inverted_index = segment_reader
+                    .inverted_index(field)
+                    .context(SearchDocument)?;
+                let mut terms = inverted_index
+                    .terms()
+                    .range()
+                    .ge(prefix.as_bytes())
+                    .into_stream()
+                    .context(ReadTermDict)?;
+                while terms.advance() {
+                    if !terms.key().starts_with(prefix.as_bytes()) {
+                        break;
+                    }
+                    if let Ok(term) = std::str::from_utf8(terms.key()) {
+                        *doc_freqs.entry(term.to_string()).or_default() += terms.value().doc_freq;
+                    }
+                }
+            }
+        }
+
+        let mut suggestions: Vec<Suggestion> = doc_freqs
+            .into_iter()
+            .map(|(text, doc_freq)| Suggestion { text, doc_freq })
+            .collect();
+        suggestions.sort_by(|a, b| {
+            b.doc_freq
+                .cmp(&a.doc_freq)
+                .then_with(|| a.text.cmp(&b.text))
+        });
+        let Some(roots) = roots else {
+            suggestions.truncate(limit);
+            return Ok(suggestions);
+        };
+
+        // 词典不区分目录，只保留在 roots 下的文档中出现过的词，避免泄露无权访问的内容
+        let roots_query = self.folders_query(roots)?;
+        let mut allowed = Vec::new();
+        for suggestion in suggestions {
+            if allowed.len() >= limit {
+                break;
+            }
+            let mut terms: Vec<(Occur, Box<dyn Query>)> = Vec::new();
+            for name in ["name", "content"] {
+                let term = Term::from_field_text(self.field(name)?, &suggestion.text);
+                terms.push((
+                    Occur::Should,
+                    Box::new(TermQuery::new(term, IndexRecordOption::Basic)),
+                ));
+            }
+            let query = BooleanQuery::new(vec![
+                (Occur::Must, Box::new(BooleanQuery::new(terms))),
+                (Occur::Must, roots_query.box_clone()),
+            ]);
+            if searcher.search(&query, &Count).context(SearchDocument)? > 0 {
+                allowed.push(suggestion);
+            }
+        }
+        Ok(allowed)
+    }
+
+    /// 同义词扩展：关键字中的词若属于某组同义词，则替换为该组所有词的 OR 查询
+    fn expand_synonyms(keyword: &str, groups: &[Vec<String>]) -> (String, Vec<Expansion>) {
+        let mut expansions: Vec<Expansion> = Vec::new();
+        let words = keyword
+            .split_whitespace()
+            .map(|word| {
+                // 含检索语法的词不扩展
+                if word.contains(|c: char| ":()[]{}\"^~*+-".contains(c)) {
+                    return word.to_string();
+                }
+                let lowercase = word.to_lowercase();
+                let Some(group) = groups
+                    .iter()
+                    .find(|group| group.iter().any(|s| s.to_lowercase() == lowercase))
+                else {
+                    return word.to_string();
+                };
+                if !expansions.iter().any(|e| e.term == word) {
+                    expansions.push(Expansion {
+                        term: word.to_string(),
+                        synonyms: group
+                            .iter()
+                            .filter(|s| s.to_lowercase() != lowercase)
+                            .cloned()
+                            .collect(),
+                    });
+                }
+                let terms: Vec<String> = group
+                    .iter()
+                    .map(|s| format!("\"{}\"", s.replace('"', "")))
+                    .collect();
+                format!("({})", terms.join(" OR "))
+            })
+            .collect::<Vec<_>>();
+        (words.join(" "), expansions)
+    }
+
+    /// 组合文档类型、目录过滤，以及修改时间、文件大小的范围过滤
+    fn with_filters(
+        &self,
+        query: Box<dyn Query>,
+        classes: &[String],
+        options: &SearchOptions,
+    ) -> Result<Box<dyn Query>> {
+        let mut queries = vec![query];
+        if !classes.is_empty() {
+            let filter = format!("class:IN [{}]", classes.join(" "));
+            queries.push(self.parser.parse_query(&filter).context(SearchParser)?);
+        }
+        if options.modified_after.is_some() || options.modified_before.is_some() {
+            queries.push(Box::new(RangeQuery::new_u64_bounds(
+                "timestamp".to_string(),
+                options
+                    .modified_after
+                    .map_or(Bound::Unbounded, Bound::Included),
+                options
+                    .modified_before
+                    .map_or(Bound::Unbounded, Bound::Excluded),
+            )));
+        }
+        if options.min_size.is_some() || options.max_size.is_some() {
+            queries.push(Box::new(RangeQuery::new_u64_bounds(
+                "size".to_string(),
+                options.min_size.map_or(Bound::Unbounded, Bound::Included),
+                options.max_size.map_or(Bound::Unbounded, Bound::Included),
+            )));
+        }
+        if !options.folders.is_empty() {
+            queries.push(self.folders_query(&options.folders)?);
+        }
+        if let Some(roots) = &options.allowed_roots {
+            queries.push(self.folders_query(roots)?);
+        }
+        Ok(if queries.len() == 1 {
+            queries.remove(0)
+        } else {
+            Box::new(BooleanQuery::intersection(queries))
+        })
+    }
+
+    /// 路径前缀匹配任一目录
+    fn folders_query(&self, folders: &[String]) -> Result<Box<dyn Query>> {
+        let field = self.field("path")?;
+        let mut queries: Vec<(Occur, Box<dyn Query>)> = Vec::new();
+        for folder in folders {
+            let query = RegexQuery::from_pattern(&folder_pattern(folder), field)
+                .context(FolderQuery { folder })?;
+            queries.push((Occur::Should, Box::new(query)));
+        }
+        Ok(Box::new(BooleanQuery::new(queries)))
+    }
+
+    /// 按排序选项收集文档：相关度排序使用评分（可按修改时间加权），其他排序使用对应的快速字段
+    fn top_docs(
+        limit: usize,
+        offset: usize,
+        options: &SearchOptions,
+        ranking: &Ranking,
+    ) -> impl Collector<Fruit = Vec<(f64, DocAddress)>> {
+        let field = options.sort_by.fast_field();
+        let order = options.order;
+        let recency = ranking.recency();
+        let now = now();
+        TopDocs::with_limit(limit).and_offset(offset).tweak_score(
+            move |segment_reader: &SegmentReader| {
+                let fast_fields = segment_reader.fast_fields();
+                let column = field
+                    .and_then(|field| fast_fields.u64(field).inspect_err(|e| error!("{e}")).ok());
+                let timestamp = recency.and_then(|_| {
+                    fast_fields
+                        .u64("timestamp")
+                        .inspect_err(|e| error!("{e}"))
+                        .ok()
+                });
+                move |doc: DocId, score: Score| {
+                    let value = match (&column, recency, &timestamp) {
+                        (Some(column), _, _) => column.first(doc).unwrap_or_default() as f64,
+                        (None, Some((half_life, weight)), Some(timestamp)) => {
+                            let timestamp = timestamp.first(doc).unwrap_or_default();
+                            score as f64 * recency_factor(now, timestamp, half_life, weight)
+                        }
+                        _ => score as f64,
+                    };
+                    match order {
+                        SortOrder::Desc => value,
+                        SortOrder::Asc => -value,
+                    }
+                }
+            },
+        )
+    }
+
+    fn field(&self, name: &str) -> Result<Field> {
+        self.schema.get_field(name).context(FieldNotFound { name })
+    }
+
+    fn to_docx(&self, doc: &Document) -> Docx {
+        Docx {
+            id: Self::get_field_value(doc, &self.schema, "id"),
+            name: Self::get_field_value(doc, &self.schema, "name"),
+            content: Self::get_field_value(doc, &self.schema, "content"),
+            path: Self::get_field_value(doc, &self.schema, "path"),
+            timestamp: Self::get_u64_value(doc, &self.schema, "timestamp"),
+            size: Self::get_u64_value(doc, &self.schema, "size"),
+            ..Default::default()
+        }
+    }
+
+    /// 读取字符串快速字段，值为空时返回 None
+    fn get_fast_str(searcher: &Searcher, doc_address: DocAddress, name: &str) -> Option<String> {
+        let column = searcher
+            .segment_reader(doc_address.segment_ord)
+            .fast_fields()
+            .str(name)
+            .inspect_err(|e| error!("{e}"))
+            .ok()??;
+        let ord = column.term_ords(doc_address.doc_id).next()?;
+        let mut value = String::new();
+        match column.ord_to_str(ord, &mut value) {
+            Ok(true) if !value.is_empty() => Some(value),
+            _ => None,
+        }
+    }
+
+    fn get_u64_value(doc: &Document, schema: &Schema, name: &str) -> u64 {
+        if let Ok(field) = schema.get_field(name).inspect_err(|e| error!("{e}")) {
+            doc.get_first(field)
+                .and_then(|v| v.as_u64())
+                .unwrap_or_default()
+        } else {
+            0
+        }
+    }
+
+    fn get_field_value(doc: &Document, schema: &Schema, name: &str) -> String {
+        if let Ok(field) = schema.get_field(name).inspect_err(|e| error!("{e}")) {
+            doc.get_first(field)
+                .and_then(|v| v.as_text())
+                .map(|s| s.to_owned())
+                .unwrap_or_default()
+        } else {
+            String::new()
+        }
+    }
+
+    async fn add_document(
+        writer: &mut IndexWriter,
+        analyzer: &mut TextAnalyzer,
+        mut docx: Docx,
+    ) -> Result<()> {
+        // id field must 0
+        let field = Field::from_field_id(0);
+        let term = Term::from_field_text(field, docx.get_id());
+        docx.set_content().await.context(OpenOrReadDocument)?;
+        docx.set_pinyin(analyzer);
+        docx.set_fingerprint(analyzer);
+        // 先删
+        let opers = vec![UserOperation::Delete(term), UserOperation::Add(docx.into())];
+        writer.run(opers).context(AddDocument)?;
+        Ok(())
+    }
+
+    fn exists(searcher: &Searcher, parser: &QueryParser, docx: &Docx) -> bool {
+        if let Ok(query) = parser
+            .parse_query(&format!(
+                "id:{} AND timestamp:{}",
+                docx.get_id(),
+                docx.get_timestamp()
+            ))
+            .inspect_err(|e| error!("{e}"))
+        {
+            if let Ok(count) = searcher.search(&query, &Count) {
+                return count > 0;
+            }
+        }
+
+        false
+    }
+}
+
+/// 匹配目录下所有文件路径的正则：目录以分隔符结尾，避免 /a/b 匹配到 /a/bc 下的文件
+fn folder_pattern(folder: &str) -> String {
+    let prefix = format!(
+        "{}{}",
+        folder.trim_end_matches(['/', '\\']),
+        std::path::MAIN_SEPARATOR
+    );
+    format!("{}.*", regex::escape(&prefix))
+}
+
+/// 路径是否位于任一根目录下；含 .. 的路径可能跳出根目录，一律视为不在根目录下
+pub fn is_under_roots(path: &str, roots: &[String]) -> bool {
+    let path = Path::new(path);
+    if path.components().any(|c| c == Component::ParentDir) {
+        return false;
+    }
+    roots.iter().any(|root| path.starts_with(root))
+}
+
+/// 当前 UNIX 时间戳（秒）
+pub fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+/// 新近度加权系数：越新的文档加权越高，每经过一个半衰期，加权减半
+fn recency_factor(now: u64, timestamp: u64, half_life: f64, weight: f64) -> f64 {
+    let age = now.saturating_sub(timestamp);
+    1.0 + weight * 0.5f64.powf(age as f64 / half_life)
+}
+
+type Result<T> = core::result::Result<T, Error>;
+
+#[derive(Debug, Snafu, CommandError)]
+pub enum Error {
+    #[snafu(display("遍历文档失败"), context(suffix(false)))]
+    #[command_error(code = "search.ReadDir")]
+    ReadDir { source: std::io::Error },
+
+    #[snafu(display("无法打开或读取文件"), context(suffix(false)))]
+    #[command_error(code = "search.OpenOrReadDocument")]
+    OpenOrReadDocument { source: crate::structs::Error },
+
+    #[snafu(display("创建 WRITER 失败"), context(suffix(false)))]
+    #[command_error(code = "search.CreateWriter")]
+    CreateWriter { source: TantivyError },
+
+    #[snafu(display("创建分词器失败"), context(suffix(false)))]
+    #[command_error(code = "search.CreateTokenizer")]
+    CreateTokenizer { source: crate::tokenizer::Error },
+
+    #[snafu(display("未找到分词器：{name}"), context(suffix(false)))]
+    #[command_error(code = "search.TokenizerNotFound")]
+    TokenizerNotFound { name: String },
+
+    #[snafu(display("添加索引文档失败"), context(suffix(false)))]
+    #[command_error(code = "search.AddDocument")]
+    AddDocument { source: TantivyError },
+
+    #[snafu(display("删除索引文档失败"), context(suffix(false)))]
+    #[command_error(code = "search.DeleteDocument")]
+    DeleteDocument { source: TantivyError },
+
+    #[snafu(display("提交索引文档失败"), context(suffix(false)))]
+    #[command_error(code = "search.Commit")]
+    Commit { source: TantivyError },
+
+    #[snafu(display("刷新索引失败"), context(suffix(false)))]
+    #[command_error(code = "search.ReloadReader")]
+    ReloadReader { source: TantivyError },
+
+    #[snafu(display("解析检索语句失败"), context(suffix(false)))]
+    #[command_error(code = "search.SearchParser")]
+    SearchParser { source: QueryParserError },
+
+    #[snafu(display("未找到字段：{name}"), context(suffix(false)))]
+    #[command_error(code = "search.FieldNotFound")]
+    FieldNotFound { source: TantivyError, name: String },
+
+    #[snafu(display("正则表达式有误：{pattern}"), context(suffix(false)))]
+    #[command_error(code = "search.RegexPattern")]
+    RegexPattern {
+        source: TantivyError,
+        pattern: String,
+    },
+
+    #[snafu(display("无法按目录检索：{folder}"), context(suffix(false)))]
+    #[command_error(code = "search.FolderQuery")]
+    FolderQuery {
+        source: TantivyError,
+        folder: String,
+    },
+
+    #[snafu(display("读取词典失败"), context(suffix(false)))]
+    #[command_error(code = "search.ReadTermDict")]
+    ReadTermDict { source: std::io::Error },
+
+    #[snafu(display("未找到文档：{id}"), context(suffix(false)))]
+    #[command_error(code = "search.DocumentNotFound")]
+    DocumentNotFound { id: String },
+
+    #[snafu(display("无法解释文档评分：{id}"), context(suffix(false)))]
+    #[command_error(code = "search.ExplainScore")]
+    ExplainScore { source: TantivyError, id: String },
+
+    #[snafu(display("检索文档失败"), context(suffix(false)))]
+    #[command_error(code = "search.SearchDocument")]
+    SearchDocument { source: TantivyError },
+
+    #[snafu(display("每页文档数至少为 1"), context(suffix(false)))]
+    #[command_error(code = "search.InvalidLimit")]
+    InvalidLimit,
+
+    #[snafu(display("无法读取或保存配置"), context(suffix(false)))]
+    #[command_error(code = "search.LoadConfig")]
+    LoadConfig { source: crate::config::Error },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ChineseVariant;
+
+    /// 内存中的索引，每个文档单独提交，使文档分布在不同的段中
+    fn state(docs: Vec<Docx>) -> SearchState {
+        state_with(&Config::default(), docs)
+    }
+
+    fn state_with(config: &Config, docs: Vec<Docx>) -> SearchState {
+        let analyzer = tokenizer::analyzer(config).unwrap();
+        let index = SearchState::builder(analyzer).create_in_ram().unwrap();
+        let mut writer: IndexWriter = index.writer(15_000_000).unwrap();
+        for docx in docs {
+            writer.add_document(docx.into()).unwrap();
+            writer.commit().unwrap();
+        }
+        let state = SearchState::with_index(index, StartupStatus::default());
+        state.reader.reload().unwrap();
+        state
+    }
+
+    fn docx(name: &str, content: &str) -> Docx {
+        Docx {
+            id: name.to_string(),
+            name: name.to_string(),
+            path: format!("/docs/{name}"),
+            content: content.to_string(),
+            class: "txt".to_string(),
+            name_key: name.to_lowercase(),
+            ..Default::default()
+        }
+    }
+
+    fn names(fruit: &SearchFruit) -> Vec<&str> {
+        fruit
+            .results
+            .iter()
+            .map(|hit| hit.doc.name.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn substring_across_token_boundaries() {
+        let state = state(vec![
+            docx("a.sql", "select * from t_user_0;"),
+            docx("b.sql", "select * from t_order_0;"),
+            docx("C_USER_NOTES.txt", ""),
+        ]);
+        let config = Config::default();
+        let options = SearchOptions {
+            mode: SearchMode::Substring,
+            ..Default::default()
+        };
+        let fruit = state
+            .search("_user_".to_string(), 0, 10, None, &options, &config)
+            .unwrap();
+        let mut found = names(&fruit);
+        found.sort();
+        assert_eq!(found, ["C_USER_NOTES.txt", "a.sql"]);
+        assert_eq!(fruit.total, 2);
+
+        let fruit = state
+            .search("_usr_".to_string(), 0, 10, None, &options, &config)
+            .unwrap();
+        assert_eq!(fruit.total, 0);
+    }
+
+    #[test]
+    fn substring_cache_follows_index_changes() {
+        let state = state(vec![docx("a.sql", "select * from t_user_0;")]);
+        let config = Config::default();
+        let options = SearchOptions {
+            mode: SearchMode::Substring,
+            ..Default::default()
+        };
+        let search = |offset| {
+            state
+                .search("_user_".to_string(), offset, 1, None, &options, &config)
+                .unwrap()
+        };
+        assert_eq!(search(0).total, 1);
+
+        let mut writer: IndexWriter = state.index.writer(15_000_000).unwrap();
+        writer
+            .add_document(docx("b.sql", "delete from t_user_1;").into())
+            .unwrap();
+        writer.commit().unwrap();
+        state.reader.reload().unwrap();
+
+        let first = search(0);
+        assert_eq!(first.total, 2);
+        let second = search(1);
+        assert_eq!(second.total, 2);
+        let mut found = [names(&first), names(&second)].concat();
+        found.sort();
+        assert_eq!(found, ["a.sql", "b.sql"]);
+    }
+
+    #[test]
+    fn sort_by_name_with_long_common_prefix() {
+        let state = state(vec![
+            docx("report_2023_q2.txt", ""),
+            docx("Report_2023_q3.txt", ""),
+            docx("report_2023_q1.txt", ""),
+        ]);
+        let config = Config::default();
+        let mut options = SearchOptions {
+            sort_by: SortBy::Name,
+            order: SortOrder::Asc,
+            ..Default::default()
+        };
+        let fruit = state
+            .search(String::new(), 0, 10, None, &options, &config)
+            .unwrap();
+        assert_eq!(
+            names(&fruit),
+            [
+                "report_2023_q1.txt",
+                "report_2023_q2.txt",
+                "Report_2023_q3.txt"
+            ]
+        );
+
+        options.order = SortOrder::Desc;
+        let fruit = state
+            .search(String::new(), 0, 10, None, &options, &config)
+            .unwrap();
+        assert_eq!(
+            names(&fruit),
+            [
+                "Report_2023_q3.txt",
+                "report_2023_q2.txt",
+                "report_2023_q1.txt"
+            ]
+        );
+
+        let fruit = state
+            .search(String::new(), 1, 1, None, &options, &config)
+            .unwrap();
+        assert_eq!(fruit.total, 3);
+        assert_eq!(names(&fruit), ["report_2023_q2.txt"]);
+    }
+
+    #[test]
+    fn simplified_query_finds_traditional_document() {
+        let config = Config {
+            chinese_variant: ChineseVariant::Simplified,
+            ..Default::default()
+        };
+        let state = state_with(
+            &config,
+            vec![docx("a.txt", "數據庫設計說明"), docx("b.txt", "网络协议")],
+        );
+        let options = SearchOptions::default();
+        for keyword in ["数据库", "數據庫", "数据"] {
+            let fruit = state
+                .search(keyword.to_string(), 0, 10, None, &options, &config)
+                .unwrap();
+            assert_eq!(names(&fruit), ["a.txt"], "{keyword}");
+        }
+    }
+
+    #[test]
+    fn zero_limit_is_rejected() {
+        let state = state(vec![docx("a.txt", "hello"), docx("b.txt", "hello")]);
+        let config = Config::default();
+        for sort_by in [SortBy::Relevance, SortBy::Name] {
+            let options = SearchOptions {
+                sort_by,
+                ..Default::default()
+            };
+            let result = state.search(String::new(), 0, 0, None, &options, &config);
+            assert!(matches!(result, Err(Error::InvalidLimit)));
+        }
+        let result = state.similar("a.txt", 0, 0, None);
+        assert!(matches!(result, Err(Error::InvalidLimit)));
+    }
+
+    fn roots(roots: &[&str]) -> Vec<String> {
+        roots.iter().map(|root| root.to_string()).collect()
+    }
+
+    #[test]
+    fn under_allowed_root() {
+        let roots = roots(&["/a/b", "/c/"]);
+        assert!(is_under_roots("/a/b/x.docx", &roots));
+        assert!(is_under_roots("/a/b/d/x.docx", &roots));
+        assert!(is_under_roots("/c/x.docx", &roots));
+    }
+
+    #[test]
+    fn outside_roots_denied() {
+        let roots = roots(&["/a/b"]);
+        assert!(!is_under_roots("/a/x.docx", &roots));
+        assert!(!is_under_roots("/d/b/x.docx", &roots));
+        assert!(!is_under_roots("/a/bc/x.docx", &roots));
+    }
+
+    #[test]
+    fn parent_dir_escapes_are_denied() {
+        let roots = roots(&["/a/b"]);
+        assert!(!is_under_roots("/a/b/../bc/x.docx", &roots));
+        assert!(!is_under_roots("/a/b/../../etc", &roots));
+        assert!(!is_under_roots("/a/b/d/../x.docx", &roots));
+        assert!(is_under_roots("/a/b/./d/x.docx", &roots));
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn folder_pattern_excludes_sibling_prefix() {
+        for folder in ["/a/b", "/a/b/"] {
+            let pattern = regex::Regex::new(&format!("^(?:{})$", folder_pattern(folder))).unwrap();
+            assert!(pattern.is_match("/a/b/x.docx"));
+            assert!(pattern.is_match("/a/b/d/x.docx"));
+            assert!(!pattern.is_match("/a/bc/x.docx"));
+            assert!(!pattern.is_match("/a/b"));
+            assert!(!pattern.is_match("/x/a/b/x.docx"));
+        }
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn folder_pattern_escapes_regex() {
+        let pattern = regex::Regex::new(&format!("^(?:{})$", folder_pattern("/a.b (1)"))).unwrap();
+        assert!(pattern.is_match("/a.b (1)/x.docx"));
+        assert!(!pattern.is_match("/axb (1)/x.docx"));
+    }
+}
@@ -0,0 +1,529 @@
+use std::process::Output;
+use std::{
+    ffi::OsStr,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use async_walkdir::DirEntry;
+use encoding::all::GBK;
+use encoding::{DecoderTrap, Encoding};
+use serde::{Deserialize, Serialize};
+use snafu::prelude::*;
+use tantivy::tokenizer::TextAnalyzer;
+use tantivy_macro::Schema;
+use tokio::{fs::File, io::AsyncReadExt, process::Command};
+use tracing::{error, info, instrument};
+use word_index_macros::CommandError;
+
+use crate::command_result::CommandError;
+use crate::fingerprint;
+
+const PLAIN_FILE_TYPE: [&str; 2] = ["txt", "sql"];
+const HYPER_FILE_TYPE: [&str; 2] = ["docx", "md"];
+
+#[derive(Serialize)]
+pub struct SearchFruit {
+    pub results: Vec<SearchHit>,
+    pub total: usize,
+    pub limit: usize,
+    pub offset: usize,
+    /// 同义词扩展情况，便于用户了解文档为何被命中
+    pub expansions: Vec<Expansion>,
+    pub facets: Facets,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct SearchHit {
+    #[serde(flatten)]
+    pub doc: Docx,
+    /// 内容相同的其他文档路径，仅在合并重复文档时返回
+    pub duplicates: Vec<String>,
+}
+
+impl From<Docx> for SearchHit {
+    fn from(doc: Docx) -> Self {
+        Self {
+            doc,
+            duplicates: Vec::new(),
+        }
+    }
+}
+
+/// 索引统计
+#[derive(Serialize, Clone, Debug)]
+pub struct IndexStats {
+    pub docs: u64,
+    pub segments: usize,
+    pub classes: Vec<FacetCount>,
+    pub folders: Vec<FacetCount>,
+}
+
+/// 启动时需告知用户的状态
+#[derive(Serialize, Clone, Debug, Default)]
+pub struct StartupStatus {
+    /// 索引结构已变更，旧索引已清空，需为所有路径重新创建索引
+    pub rebuilt: bool,
+    /// 无法加载用户词典或停用词，此时使用内置词典
+    pub tokenizer_error: Option<CommandError>,
+}
+
+/// 重复文档
+#[derive(Serialize, Clone, Debug)]
+pub struct DuplicateCluster {
+    pub kind: DuplicateKind,
+    pub paths: Vec<String>,
+}
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum DuplicateKind {
+    /// 内容完全相同
+    Exact,
+    /// 内容近似
+    Near,
+}
+
+/// 命中文档按类型、顶层目录、修改年份的分布
+#[derive(Serialize, Clone, Debug, Default)]
+pub struct Facets {
+    pub classes: Vec<FacetCount>,
+    pub folders: Vec<FacetCount>,
+    pub years: Vec<FacetCount>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct FacetCount {
+    pub value: String,
+    pub count: usize,
+}
+
+/// 检索词补全
+#[derive(Serialize, Clone, Debug)]
+pub struct Suggestion {
+    pub text: String,
+    pub doc_freq: u32,
+}
+
+/// 检索选项
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+#[serde(default, rename_all = "camelCase")]
+pub struct SearchOptions {
+    pub sort_by: SortBy,
+    pub order: SortOrder,
+    /// 修改时间不早于，UNIX 时间戳（秒）
+    pub modified_after: Option<u64>,
+    /// 修改时间早于，UNIX 时间戳（秒）
+    pub modified_before: Option<u64>,
+    /// 最小文件大小（字节）
+    pub min_size: Option<u64>,
+    /// 最大文件大小（字节）
+    pub max_size: Option<u64>,
+    /// 仅检索这些目录（含子目录）下的文件
+    pub folders: Vec<String>,
+    pub mode: SearchMode,
+    /// 合并内容相同的文档
+    pub collapse: bool,
+    /// 只能检索这些根目录下的文件，由调用方按访问权限设置，不接受客户端传入
+    #[serde(skip)]
+    pub allowed_roots: Option<Vec<String>>,
+}
+
+/// 检索请求，HTTP 接口及 JSON-RPC 的 search 参数，与 search_doc_file 的参数一致
+#[derive(Deserialize, Clone, Debug)]
+pub struct SearchRequest {
+    pub keyword: String,
+    #[serde(default)]
+    pub offset: usize,
+    #[serde(default = "default_limit")]
+    pub limit: usize,
+    pub classes: Option<Vec<String>>,
+    pub options: Option<SearchOptions>,
+}
+
+/// 未指定时每页的文档数
+pub fn default_limit() -> usize {
+    10
+}
+
+/// 一次检索的关键字及过滤条件，用于检索历史和保存的检索
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+#[serde(default, rename_all = "camelCase")]
+pub struct SavedQuery {
+    pub keyword: String,
+    pub classes: Option<Vec<String>>,
+    pub options: SearchOptions,
+}
+
+/// 用户命名并固定的检索
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct SavedSearch {
+    pub name: String,
+    #[serde(flatten)]
+    pub query: SavedQuery,
+    /// 上次检查新文档的时间，UNIX 时间戳（秒）
+    #[serde(default)]
+    pub last_checked: u64,
+}
+
+/// 保存的检索在上次检查后新出现的文档
+#[derive(Serialize, Clone, Debug)]
+pub struct SearchAlert {
+    pub name: String,
+    pub total: usize,
+    pub results: Vec<SearchHit>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum SearchMode {
+    /// 关键字，支持检索语法
+    #[default]
+    Keyword,
+    /// 正则表达式，匹配单个词
+    Regex,
+    /// 精确子串，校验文件名或内容中是否包含
+    Substring,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum SortBy {
+    /// 相关度
+    #[default]
+    Relevance,
+    /// 修改时间
+    Timestamp,
+    /// 文件名
+    Name,
+    /// 文件大小
+    Size,
+}
+
+impl SortBy {
+    /// 排序所用的数值快速字段，相关度排序时为空
+    /// 文件名排序时也为空：字符串快速字段的词序号只在段内有序，需取出候选文档后按完整文件名排序
+    pub fn fast_field(&self) -> Option<&'static str> {
+        match self {
+            SortBy::Relevance | SortBy::Name => None,
+            SortBy::Timestamp => Some("timestamp"),
+            SortBy::Size => Some("size"),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum SortOrder {
+    #[default]
+    Desc,
+    Asc,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct Expansion {
+    pub term: String,
+    pub synonyms: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, Schema)]
+pub struct Docx {
+    #[field(fast, stored)]
+    pub id: String,
+    #[field(stored, tokenized)]
+    pub name: String,
+    #[field(stored, indexed)]
+    pub path: String,
+    #[field(stored, tokenized)]
+    pub content: String,
+    #[field(indexed, fast, stored)]
+    pub timestamp: u64,
+    #[field(fast)]
+    pub class: String,
+    #[field(tokenized)]
+    #[serde(skip)]
+    pub pinyin: String,
+    #[field(indexed, fast, stored)]
+    pub size: u64,
+    /// 文件名排序键：小写的完整文件名
+    #[field(fast)]
+    #[serde(skip)]
+    pub name_key: String,
+    /// 顶层目录：索引路径下的第一级子目录，文件直接位于索引路径下时为索引路径
+    #[field(fast)]
+    pub folder: String,
+    /// 内容的 md5，用于查找内容相同的文档
+    #[field(fast)]
+    #[serde(skip)]
+    pub hash: String,
+    /// 内容的 simhash，用于查找内容近似的文档
+    #[field(fast)]
+    #[serde(skip)]
+    pub simhash: u64,
+}
+
+impl Docx {
+    pub async fn new(dir_entry: &DirEntry) -> Result<Docx> {
+        let path = dir_entry.path();
+        let extension = path.extension();
+
+        ensure!(
+            is_support(dir_entry).await,
+            UnsupportedDocument {
+                path: path.to_str().unwrap().to_string()
+            }
+        );
+
+        let name = path.file_name().and_then(|s| s.to_str()).unwrap();
+        let path_name = path.to_str().unwrap();
+        let (timestamp, size) = get_file_metadata(dir_entry).await?;
+        let md5 = md5::compute(path_name.as_bytes());
+        let id = format!("{:x}", md5);
+        Ok(Self {
+            id,
+            name: name.to_string(),
+            path: path_name.to_string(),
+            content: String::new(),
+            timestamp,
+            class: extension
+                .and_then(|s| s.to_str())
+                .unwrap_or("")
+                .to_owned()
+                .to_lowercase(),
+            pinyin: String::new(),
+            size,
+            name_key: name.to_lowercase(),
+            folder: String::new(),
+            hash: String::new(),
+            simhash: 0,
+        })
+    }
+
+    pub fn get_id(&self) -> &str {
+        &self.id
+    }
+
+    pub fn get_timestamp(&self) -> u64 {
+        self.timestamp
+    }
+
+    pub async fn set_content(&mut self) -> Result<()> {
+        self.content = extract_text(&self.path).await?;
+        Ok(())
+    }
+
+    pub fn set_folder(&mut self, root: &str) {
+        let folder = Path::new(&self.path)
+            .strip_prefix(root)
+            .ok()
+            .and_then(|relative| {
+                let mut components = relative.components();
+                let first = components.next()?;
+                // 至少还有一级，说明 first 是目录
+                components.next()?;
+                Some(Path::new(root).join(first))
+            })
+            .unwrap_or_else(|| PathBuf::from(root));
+        self.folder = folder.to_string_lossy().into_owned();
+    }
+
+    /// 根据内容生成指纹
+    pub fn set_fingerprint(&mut self, analyzer: &mut TextAnalyzer) {
+        self.hash = fingerprint::content_hash(&self.content);
+        self.simhash = fingerprint::simhash(analyzer, &self.content);
+    }
+
+    /// 根据文件名的分词结果生成拼音
+    pub fn set_pinyin(&mut self, analyzer: &mut TextAnalyzer) {
+        self.pinyin = crate::tokenizer::to_pinyin(analyzer, &self.name);
+    }
+}
+
+pub async fn is_support(dir_entry: &DirEntry) -> bool {
+    if let Ok(file_type) = dir_entry.file_type().await.inspect_err(|e| error!("{e}")) {
+        if !file_type.is_file() {
+            return false;
+        }
+        let file_name = dir_entry.file_name();
+        let file_name = file_name.to_str();
+        if matches!(file_name, Some(name) if name.starts_with("~$")) {
+            return false;
+        }
+        let path = dir_entry.path();
+        if let Some(e) = path.extension() {
+            return is_plain(e) || is_hyper(e);
+        }
+    }
+    false
+}
+
+/// 提取文件的文本内容，供其他程序复用：纯文本按 UTF-8 或 GBK 读取，docx、md 通过 pandoc 转换
+pub async fn extract_text(path: &str) -> Result<String> {
+    match Path::new(path).extension() {
+        Some(extension) if is_plain(extension) => read_plain_file(path).await,
+        Some(extension) if is_hyper(extension) => read_docx_file(path).await,
+        _ => UnsupportedDocument { path }.fail(),
+    }
+}
+
+/// 是否按纯文本读取的扩展名，如 txt、sql
+pub fn is_plain(extension: &OsStr) -> bool {
+    let extension = extension.to_ascii_lowercase();
+    for e in PLAIN_FILE_TYPE {
+        if e == extension {
+            return true;
+        }
+    }
+    false
+}
+
+/// 是否需要 pandoc 转换的扩展名，如 docx、md
+pub fn is_hyper(extension: &OsStr) -> bool {
+    let extension = extension.to_ascii_lowercase();
+    for e in HYPER_FILE_TYPE {
+        if e == extension {
+            return true;
+        }
+    }
+    false
+}
+
+/// 文件时间戳及大小
+async fn get_file_metadata(dir_entry: &DirEntry) -> Result<(u64, u64)> {
+    let path = dir_entry.path();
+    let io_error = OpenOrReadDocument {
+        path: path.to_str().map(|s| s.to_string()).unwrap_or_default(),
+    };
+    let metadata = dir_entry.metadata().await.context(io_error.clone())?;
+    let timestamp = metadata
+        .modified()
+        .context(io_error.clone())?
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .inspect_err(|e| error!("{e}"))
+        .map_err(|_| Error::ComputeSystemTime)?
+        .as_secs();
+    Ok((timestamp, metadata.len()))
+}
+
+/// 调用pandoc，读取docx文件，返回文件内容
+/// 需要提前安装pandoc
+#[instrument]
+async fn read_docx_file(path: &str) -> Result<String> {
+    info!("read_docx_file");
+
+    let mut command = Command::new("pandoc");
+    let command = command.args([
+        "-t",
+        "plain",
+        "--wrap=none",
+        "--markdown-headings=atx",
+        path,
+    ]);
+
+    let output = output(command).await.context(PandocConvert {
+        path: path.to_string(),
+    })?;
+
+    String::from_utf8(output.stdout)
+        .inspect_err(|e| error!("{e}"))
+        .map_err(|_| Error::UnsupportedEncoding {
+            path: path.to_string(),
+        })
+}
+
+#[cfg(windows)]
+async fn output(command: &mut Command) -> core::result::Result<Output, std::io::Error> {
+    const CREATE_NO_WINDOW: u32 = 0x08000000;
+    command.creation_flags(CREATE_NO_WINDOW).output().await
+}
+
+#[cfg(not(windows))]
+async fn output(command: &mut Command) -> core::result::Result<Output, std::io::Error> {
+    command.output().await
+}
+
+#[instrument]
+async fn read_plain_file(path: &str) -> Result<String> {
+    if let Ok(s) = tokio::fs::read_to_string(path).await {
+        return Ok(s);
+    }
+    let io_error = OpenOrReadDocument {
+        path: path.to_string(),
+    };
+    let mut file = File::open(path).await.context(io_error.clone())?;
+    let mut buf = Vec::new();
+    file.read_to_end(&mut buf).await.context(io_error.clone())?;
+    GBK.decode(&buf, DecoderTrap::Strict)
+        .inspect_err(|e| error!("{e}"))
+        .map_err(|_| Error::UnsupportedEncoding {
+            path: path.to_string(),
+        })
+}
+
+type Result<T> = core::result::Result<T, Error>;
+
+#[derive(Debug, Snafu, CommandError)]
+pub enum Error {
+    #[snafu(display("未支持的文档类型：{path}"), context(suffix(false)))]
+    #[command_error(code = "structs.UnsupportedDocument")]
+    UnsupportedDocument { path: String },
+
+    #[snafu(
+        display("该文档非 UTF-8 或者 GBK 编码：{path}"),
+        context(suffix(false))
+    )]
+    #[command_error(code = "structs.UnsupportedEncoding")]
+    UnsupportedEncoding { path: String },
+
+    #[snafu(display("无法打开或读取文件：{path}"), context(suffix(false)))]
+    #[command_error(code = "structs.OpenOrReadDocument")]
+    OpenOrReadDocument {
+        path: String,
+        source: std::io::Error,
+    },
+
+    #[snafu(
+        display("Pandoc 无法将 word 文件转换成普通文本：{path}"),
+        context(suffix(false))
+    )]
+    #[command_error(code = "structs.PandocConvert")]
+    PandocConvert {
+        path: String,
+        source: std::io::Error,
+    },
+
+    #[snafu(display("系统时间错误"), context(suffix(false)))]
+    #[command_error(code = "structs.ComputeSystemTime")]
+    ComputeSystemTime,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn block_on<F: std::future::Future>(future: F) -> F::Output {
+        tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap()
+            .block_on(future)
+    }
+
+    #[test]
+    fn extract_plain_text() {
+        let path = std::env::temp_dir().join("word-index-extract-text.txt");
+        std::fs::write(&path, "数据库设计").unwrap();
+        let text = block_on(extract_text(path.to_str().unwrap())).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(text, "数据库设计");
+    }
+
+    #[test]
+    fn extract_unsupported_document() {
+        let result = block_on(extract_text("/docs/a.pdf"));
+        assert!(matches!(result, Err(Error::UnsupportedDocument { .. })));
+        let result = block_on(extract_text("/docs/Makefile"));
+        assert!(matches!(result, Err(Error::UnsupportedDocument { .. })));
+    }
+}
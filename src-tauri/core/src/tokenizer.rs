@@ -0,0 +1,187 @@
+use std::collections::HashSet;
+use std::fs::{read_to_string, File};
+use std::io::BufReader;
+use std::sync::Arc;
+
+use jieba_rs::{Jieba, TokenizeMode};
+use pinyin::ToPinyin;
+use snafu::prelude::*;
+use tantivy::tokenizer::{
+    LowerCaser, RemoveLongFilter, StopWordFilter, TextAnalyzer, Token, TokenStream, Tokenizer,
+};
+use word_index_macros::CommandError;
+use zhconv::{zhconv, Variant};
+
+use crate::config::{ChineseVariant, Config};
+
+/// 根据配置构建分词器：简繁统一后结巴分词（可加载自定义词典）、去除长词、转小写、去除停用词
+pub fn analyzer(config: &Config) -> Result<TextAnalyzer> {
+    let mut jieba = Jieba::new();
+    if let Some(path) = &config.user_dict {
+        let file = File::open(path).context(ReadDictFile { path })?;
+        jieba
+            .load_dict(&mut BufReader::new(file))
+            .context(LoadUserDict { path })?;
+    }
+    let stop_words = if let Some(path) = &config.stop_words {
+        read_to_string(path)
+            .context(ReadDictFile { path })?
+            .lines()
+            .map(str::trim)
+            .filter(|w| !w.is_empty())
+            .map(str::to_lowercase)
+            .collect()
+    } else {
+        Vec::new()
+    };
+
+    Ok(build(jieba, stop_words, config))
+}
+
+/// 只使用内置词典、不去除停用词的分词器，用户词典或停用词无法读取时使用
+pub fn builtin_analyzer(config: &Config) -> TextAnalyzer {
+    build(Jieba::new(), Vec::new(), config)
+}
+
+fn build(jieba: Jieba, stop_words: Vec<String>, config: &Config) -> TextAnalyzer {
+    TextAnalyzer::builder(JiebaTokenizer::new(jieba).convert(config.chinese_variant))
+        .filter(RemoveLongFilter::limit(40))
+        .filter(LowerCaser)
+        .filter(StopWordFilter::remove(stop_words))
+        .build()
+}
+
+/// 结巴分词，与 tantivy-jieba 相同，但可使用加载了自定义词典的实例
+/// 指定简繁转换时，先将整段文本转换成简体或繁体再分词，使简繁文本的分词结果一致
+#[derive(Clone)]
+pub struct JiebaTokenizer {
+    jieba: Arc<Jieba>,
+    variant: Option<Variant>,
+}
+
+impl JiebaTokenizer {
+    pub fn new(jieba: Jieba) -> Self {
+        Self {
+            jieba: Arc::new(jieba),
+            variant: None,
+        }
+    }
+
+    /// 分词前的简繁转换，索引与检索时均需使用
+    pub fn convert(mut self, variant: ChineseVariant) -> Self {
+        self.variant = match variant {
+            ChineseVariant::None => None,
+            ChineseVariant::Simplified => Some(Variant::ZhHans),
+            ChineseVariant::Traditional => Some(Variant::ZhHant),
+        };
+        self
+    }
+}
+
+impl Tokenizer for JiebaTokenizer {
+    type TokenStream<'a> = JiebaTokenStream;
+
+    fn token_stream<'a>(&'a mut self, text: &'a str) -> JiebaTokenStream {
+        // jieba 返回的是字符位置，需转换成原文的字节位置
+        let mut indices = text.char_indices().map(|(i, _)| i).collect::<Vec<_>>();
+        indices.push(text.len());
+        let converted = match self.variant {
+            Some(variant) if !text.is_ascii() => zhconv(text, variant),
+            _ => text.to_string(),
+        };
+        // 简繁转换绝大多数是逐字的，字符数不变时位置一一对应；个别词转换后字数不同，位置按原文长度截断
+        let offset = |i: usize| indices[i.min(indices.len() - 1)];
+        let tokens = self
+            .jieba
+            .tokenize(&converted, TokenizeMode::Search, true)
+            .into_iter()
+            .map(|token| Token {
+                offset_from: offset(token.start),
+                offset_to: offset(token.end),
+                position: token.start,
+                text: token.word.to_string(),
+                position_length: token.end - token.start,
+            })
+            .collect();
+        JiebaTokenStream { tokens, index: 0 }
+    }
+}
+
+pub struct JiebaTokenStream {
+    tokens: Vec<Token>,
+    index: usize,
+}
+
+impl TokenStream for JiebaTokenStream {
+    fn advance(&mut self) -> bool {
+        if self.index < self.tokens.len() {
+            self.index += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn token(&self) -> &Token {
+        &self.tokens[self.index - 1]
+    }
+
+    fn token_mut(&mut self) -> &mut Token {
+        &mut self.tokens[self.index - 1]
+    }
+}
+
+/// 根据分词结果生成拼音，包含每个词的全拼、首字母，以及整段文本的全拼、首字母
+/// 例如：数据库设计 => shujuku sjk sheji sj shujukusheji sjksj
+pub fn to_pinyin(analyzer: &mut TextAnalyzer, text: &str) -> String {
+    let mut words = Vec::new();
+    let mut stream = analyzer.token_stream(text);
+    while stream.advance() {
+        if let Some((plain, initials)) = pinyin_of(&stream.token().text) {
+            words.push(plain);
+            words.push(initials);
+        }
+    }
+    if let Some((plain, initials)) = pinyin_of(text) {
+        words.push(plain);
+        words.push(initials);
+    }
+
+    let mut seen = HashSet::new();
+    words.retain(|w| seen.insert(w.clone()));
+    words.join(" ")
+}
+
+/// 返回全拼和首字母，非汉字字符将被忽略
+fn pinyin_of(text: &str) -> Option<(String, String)> {
+    let mut plain = String::new();
+    let mut initials = String::new();
+    for p in text.to_pinyin().flatten() {
+        plain.push_str(p.plain());
+        initials.push_str(p.first_letter());
+    }
+    if plain.is_empty() {
+        None
+    } else {
+        Some((plain, initials))
+    }
+}
+
+type Result<T> = core::result::Result<T, Error>;
+
+#[derive(Debug, Snafu, CommandError)]
+pub enum Error {
+    #[snafu(display("无法读取词典文件：{path}"), context(suffix(false)))]
+    #[command_error(code = "tokenizer.ReadDictFile")]
+    ReadDictFile {
+        source: std::io::Error,
+        path: String,
+    },
+
+    #[snafu(display("无法加载自定义词典：{path}"), context(suffix(false)))]
+    #[command_error(code = "tokenizer.LoadUserDict")]
+    LoadUserDict {
+        source: jieba_rs::Error,
+        path: String,
+    },
+}
@@ -0,0 +1,85 @@
+use proc_macro::TokenStream;
+use quote::quote;
+use syn::{parse_macro_input, Data, DeriveInput, Fields, LitStr, Variant};
+
+/// 之所以将代码放到lib，是因为 proc-macros 必须在 proc-macro crate 中定义；
+/// 为 snafu 错误枚举生成到 CommandError 的转换：
+/// 错误码由每个变体的 #[command_error(code = "search.DocumentNotFound")] 指定，不随模块或变体重命名而改变；
+/// 除 source、backtrace 外的字段作为 details，source 链作为 sources
+#[proc_macro_derive(CommandError, attributes(command_error))]
+pub fn derive_command_error(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    let name = input.ident;
+    let Data::Enum(data) = input.data else {
+        return syn::Error::new_spanned(&name, "CommandError 只能用于枚举")
+            .to_compile_error()
+            .into();
+    };
+
+    let mut arms = Vec::new();
+    for variant in &data.variants {
+        let ident = &variant.ident;
+        let code = match code(variant) {
+            Ok(code) => code,
+            Err(e) => return e.to_compile_error().into(),
+        };
+        let arm = match &variant.fields {
+            Fields::Named(fields) => {
+                let names: Vec<_> = fields
+                    .named
+                    .iter()
+                    .filter_map(|field| field.ident.as_ref())
+                    .filter(|ident| *ident != "source" && *ident != "backtrace")
+                    .collect();
+                let keys = names.iter().map(|ident| ident.to_string());
+                quote! {
+                    #name::#ident { #(#names,)* .. } => (
+                        #code,
+                        vec![#((#keys.to_string(), #names.to_string()),)*],
+                    )
+                }
+            }
+            Fields::Unnamed(_) => quote! { #name::#ident(..) => (#code, Vec::new()) },
+            Fields::Unit => quote! { #name::#ident => (#code, Vec::new()) },
+        };
+        arms.push(arm);
+    }
+
+    let expanded = quote! {
+        impl std::convert::From<#name> for ::word_index_core::CommandError {
+            fn from(e: #name) -> ::word_index_core::CommandError {
+                let (code, details) = match &e {
+                    #(#arms,)*
+                };
+                ::word_index_core::CommandError::from_error(code, details, &e)
+            }
+        }
+    };
+
+    TokenStream::from(expanded)
+}
+
+/// 读取变体的 #[command_error(code = "...")]，未指定时报错
+fn code(variant: &Variant) -> syn::Result<LitStr> {
+    let mut code = None;
+    for attr in &variant.attrs {
+        if !attr.path().is_ident("command_error") {
+            continue;
+        }
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("code") {
+                code = Some(meta.value()?.parse::<LitStr>()?);
+                Ok(())
+            } else {
+                Err(meta.error("只支持 code"))
+            }
+        })?;
+    }
+    code.ok_or_else(|| {
+        syn::Error::new_spanned(
+            &variant.ident,
+            "缺少错误码，如 #[command_error(code = \"search.DocumentNotFound\")]",
+        )
+    })
+}
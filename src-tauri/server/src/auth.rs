@@ -0,0 +1,225 @@
+use axum::{
+    async_trait,
+    body::Body,
+    extract::{FromRequestParts, Query, State},
+    http::{header, request::Parts, Request, StatusCode},
+    middleware::Next,
+    response::Response,
+};
+use serde::Deserialize;
+use tracing::info;
+use word_index_core::config::ApiToken;
+use word_index_core::{CommandError, Config};
+
+use crate::ApiError;
+
+/// 调用方可访问的根目录，未配置访问令牌时可访问全部
+pub(crate) struct Caller {
+    pub roots: Option<Vec<String>>,
+    /// 是否携带了可访问全部根目录的令牌
+    admin: bool,
+}
+
+impl Caller {
+    /// 创建索引等修改操作需要携带可访问全部根目录的令牌；未配置访问令牌时，本机任意进程均可访问接口，不允许修改
+    pub fn ensure_admin(&self) -> Result<(), ApiError> {
+        if !self.admin {
+            return Err(ApiError(
+                StatusCode::FORBIDDEN,
+                CommandError::new("server.Forbidden", "无权执行该操作！"),
+            ));
+        }
+        Ok(())
+    }
+
+    /// 从 Authorization: Bearer 请求头读取令牌；下载链接无法设置请求头，也可使用 token 参数
+    fn from_tokens(tokens: Vec<ApiToken>, parts: &Parts) -> Result<Self, ApiError> {
+        if tokens.is_empty() {
+            return Ok(Self {
+                roots: None,
+                admin: false,
+            });
+        }
+
+        let token = parts
+            .headers
+            .get(header::AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .map(String::from)
+            .or_else(|| {
+                Query::<TokenParams>::try_from_uri(&parts.uri)
+                    .ok()
+                    .and_then(|Query(params)| params.token)
+            })
+            .filter(|token| !token.is_empty());
+        let api_token = token
+            .and_then(|token| tokens.into_iter().find(|t| t.token == token))
+            .ok_or_else(|| {
+                ApiError(
+                    StatusCode::UNAUTHORIZED,
+                    CommandError::new("server.Unauthorized", "访问令牌无效！"),
+                )
+            })?;
+        info!("{} {}", api_token.name, parts.uri.path());
+        Ok(Self {
+            admin: api_token.roots.is_none(),
+            roots: api_token.roots,
+        })
+    }
+}
+
+/// 只接受 Host 为 127.0.0.1:<port> 或 localhost:<port> 的请求
+/// 只监听 127.0.0.1 并不能阻止 DNS 重绑定：恶意网页将自己的域名解析到 127.0.0.1 后即可读取接口
+pub(crate) async fn check_host(
+    State(port): State<u16>,
+    request: Request<Body>,
+    next: Next<Body>,
+) -> Result<Response, ApiError> {
+    let host = request
+        .headers()
+        .get(header::HOST)
+        .and_then(|value| value.to_str().ok());
+    if !host.is_some_and(|host| is_local_host(host, port)) {
+        return Err(ApiError(
+            StatusCode::FORBIDDEN,
+            CommandError::new(
+                "server.InvalidHost",
+                "只能通过 127.0.0.1 或 localhost 访问！",
+            )
+            .with_detail("host", host.unwrap_or_default()),
+        ));
+    }
+    Ok(next.run(request).await)
+}
+
+fn is_local_host(host: &str, port: u16) -> bool {
+    let Some((name, host_port)) = host.rsplit_once(':') else {
+        return false;
+    };
+    host_port == port.to_string() && (name == "127.0.0.1" || name.eq_ignore_ascii_case("localhost"))
+}
+
+#[derive(Deserialize)]
+struct TokenParams {
+    token: Option<String>,
+}
+
+#[async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for Caller {
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, ApiError> {
+        let config = Config::load().await?;
+        Self::from_tokens(config.server.tokens, parts)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::http::Request;
+
+    use super::*;
+
+    fn tokens() -> Vec<ApiToken> {
+        vec![
+            ApiToken {
+                name: "admin".to_string(),
+                token: "admin-token".to_string(),
+                roots: None,
+            },
+            ApiToken {
+                name: "hr".to_string(),
+                token: "hr-token".to_string(),
+                roots: Some(vec!["/data/hr".to_string()]),
+            },
+        ]
+    }
+
+    fn parts(uri: &str, authorization: Option<&str>) -> Parts {
+        let mut builder = Request::builder().uri(uri);
+        if let Some(value) = authorization {
+            builder = builder.header(header::AUTHORIZATION, value);
+        }
+        builder.body(()).unwrap().into_parts().0
+    }
+
+    fn status(result: Result<Caller, ApiError>) -> StatusCode {
+        match result {
+            Ok(_) => StatusCode::OK,
+            Err(ApiError(status, _)) => status,
+        }
+    }
+
+    #[test]
+    fn no_tokens_allows_all_roots() {
+        let caller = Caller::from_tokens(Vec::new(), &parts("/api/search", None)).unwrap();
+        assert_eq!(caller.roots, None);
+        assert!(matches!(
+            caller.ensure_admin(),
+            Err(ApiError(StatusCode::FORBIDDEN, _))
+        ));
+    }
+
+    #[test]
+    fn admin_token_allows_modification() {
+        let parts = parts("/api/index", Some("Bearer admin-token"));
+        let caller = Caller::from_tokens(tokens(), &parts).unwrap();
+        assert!(caller.ensure_admin().is_ok());
+    }
+
+    #[test]
+    fn bearer_token_restricts_roots() {
+        let parts = parts("/api/search", Some("Bearer hr-token"));
+        let caller = Caller::from_tokens(tokens(), &parts).unwrap();
+        assert_eq!(caller.roots, Some(vec!["/data/hr".to_string()]));
+        assert!(matches!(
+            caller.ensure_admin(),
+            Err(ApiError(StatusCode::FORBIDDEN, _))
+        ));
+    }
+
+    #[test]
+    fn query_token_is_accepted() {
+        let parts = parts("/api/documents/1/download?token=admin-token", None);
+        let caller = Caller::from_tokens(tokens(), &parts).unwrap();
+        assert_eq!(caller.roots, None);
+    }
+
+    #[test]
+    fn missing_token_is_unauthorized() {
+        let result = Caller::from_tokens(tokens(), &parts("/api/search", None));
+        assert_eq!(status(result), StatusCode::UNAUTHORIZED);
+    }
+
+    #[test]
+    fn only_local_hosts_are_accepted() {
+        assert!(is_local_host("127.0.0.1:8787", 8787));
+        assert!(is_local_host("localhost:8787", 8787));
+        assert!(is_local_host("LocalHost:8787", 8787));
+        assert!(!is_local_host("127.0.0.1:8788", 8787));
+        assert!(!is_local_host("127.0.0.1", 8787));
+        assert!(!is_local_host("evil.example:8787", 8787));
+        assert!(!is_local_host("localhost.evil.example:8787", 8787));
+        assert!(!is_local_host("[::1]:8787", 8787));
+    }
+
+    #[test]
+    fn invalid_token_is_unauthorized() {
+        let bearer = parts("/api/search", Some("Bearer nope"));
+        assert_eq!(
+            status(Caller::from_tokens(tokens(), &bearer)),
+            StatusCode::UNAUTHORIZED
+        );
+        let empty = parts("/api/search?token=", Some("Bearer "));
+        assert_eq!(
+            status(Caller::from_tokens(tokens(), &empty)),
+            StatusCode::UNAUTHORIZED
+        );
+        let basic = parts("/api/search", Some("Basic hr-token"));
+        assert_eq!(
+            status(Caller::from_tokens(tokens(), &basic)),
+            StatusCode::UNAUTHORIZED
+        );
+    }
+}
@@ -0,0 +1,338 @@
+//! 本地 HTTP JSON 接口，与桌面版、命令行版共用 SearchState，语义与桌面版的命令一致
+//! 指定网页目录时，同时提供网页版检索页面，此时接口只读，不能创建索引
+//! 配置了访问令牌时，需携带令牌访问接口，且只能访问令牌对应的根目录
+//! 创建索引需携带可访问全部根目录的令牌，且只能为配置中的索引路径创建索引
+//! 只接受 Host 为 127.0.0.1 或 localhost 的请求，防止 DNS 重绑定
+use std::net::SocketAddr;
+use std::path::{Path as FilePath, PathBuf};
+
+use axum::{
+    extract::{Path, Query, State},
+    http::{header, StatusCode},
+    middleware,
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Json, Router,
+};
+use serde::{Deserialize, Serialize};
+use snafu::prelude::*;
+use tower_http::services::{ServeDir, ServeFile};
+use tracing::{error, info};
+use word_index_core::structs::{
+    default_limit, Docx, IndexStats, SearchFruit, SearchOptions, SearchRequest, Suggestion,
+};
+use word_index_core::tantivy::query::Explanation;
+use word_index_core::{search, CommandError, Config, SearchState};
+use word_index_macros::CommandError;
+
+use crate::auth::Caller;
+
+mod auth;
+mod opensearch;
+
+/// port 为监听的端口，用于生成 opensearch.xml 中的地址
+pub fn router(state: SearchState, port: u16, web_root: Option<&FilePath>) -> Router {
+    let router = Router::new()
+        .route("/api/search", post(search))
+        .route("/api/explain", post(explain))
+        .route("/api/suggest", get(suggest))
+        .route("/api/status", get(status))
+        .route("/api/documents/:id", get(document))
+        .route("/api/documents/:id/similar", get(similar))
+        .route("/api/documents/:id/download", get(download))
+        .route("/api/opensearch/suggest", get(opensearch::suggestions));
+    let router = match web_root {
+        Some(root) => {
+            // 单页应用，未找到的路径均返回 index.html
+            let index_html = ServeFile::new(root.join("index.html"));
+            router
+                .route(
+                    "/opensearch.xml",
+                    get(move |params| opensearch::description(port, params)),
+                )
+                .fallback_service(ServeDir::new(root).fallback(index_html))
+        }
+        None => router.route("/api/index", post(index)),
+    };
+    router
+        .with_state(state)
+        .layer(middleware::from_fn_with_state(port, auth::check_host))
+}
+
+/// 在 127.0.0.1 的指定端口上启动 HTTP 接口，直到出错才返回
+pub async fn serve(state: SearchState, port: u16, web_root: Option<PathBuf>) -> Result<()> {
+    let address = SocketAddr::from(([127, 0, 0, 1], port));
+    let server = hyper::Server::try_bind(&address).context(Bind { address })?;
+    info!("HTTP 接口已启动：http://{address}");
+    server
+        .serve(router(state, port, web_root.as_deref()).into_make_service())
+        .await
+        .context(Serve)
+}
+
+#[derive(Deserialize)]
+struct IndexRequest {
+    path: String,
+}
+
+/// 为指定路径的文件创建索引，与 index_doc_file 相同；路径需位于配置的索引路径下
+async fn index(
+    State(state): State<SearchState>,
+    caller: Caller,
+    Json(request): Json<IndexRequest>,
+) -> ApiResult<()> {
+    caller.ensure_admin()?;
+    let config = Config::load().await?;
+    if !search::is_under_roots(&request.path, &config.paths) {
+        return Err(ApiError(
+            StatusCode::FORBIDDEN,
+            CommandError::new(
+                "server.PathNotConfigured",
+                format!("{}\n不在配置的索引路径下！", request.path),
+            )
+            .with_detail("path", &request.path),
+        ));
+    }
+    let handle = tokio::runtime::Handle::current();
+    tokio::task::spawn_blocking(move || handle.block_on(state.index(request.path))).await??;
+    Ok(Json(()))
+}
+
+/// 搜索文件，支持分页，与 search_doc_file 相同
+async fn search(
+    State(state): State<SearchState>,
+    caller: Caller,
+    Json(request): Json<SearchRequest>,
+) -> ApiResult<SearchFruit> {
+    let config = Config::load().await?;
+    let mut options = request.options.unwrap_or_default();
+    options.allowed_roots = caller.roots;
+    let fruit = state.search(
+        request.keyword,
+        request.offset,
+        request.limit,
+        request.classes,
+        &options,
+        &config,
+    )?;
+    Ok(Json(fruit))
+}
+
+#[derive(Deserialize)]
+struct ExplainRequest {
+    keyword: String,
+    id: String,
+    classes: Option<Vec<String>>,
+    options: Option<SearchOptions>,
+}
+
+/// 解释文档在本次检索中的评分，与 explain_doc_file 相同
+async fn explain(
+    State(state): State<SearchState>,
+    caller: Caller,
+    Json(request): Json<ExplainRequest>,
+) -> ApiResult<Explanation> {
+    let config = Config::load().await?;
+    let mut options = request.options.unwrap_or_default();
+    options.allowed_roots = caller.roots;
+    let explanation = state.explain(
+        request.keyword,
+        &request.id,
+        request.classes,
+        &options,
+        &config,
+    )?;
+    Ok(Json(explanation))
+}
+
+#[derive(Deserialize)]
+struct SuggestParams {
+    prefix: String,
+    limit: Option<usize>,
+}
+
+/// 检索词补全，与 suggest 相同
+async fn suggest(
+    State(state): State<SearchState>,
+    caller: Caller,
+    Query(params): Query<SuggestParams>,
+) -> ApiResult<Vec<Suggestion>> {
+    let limit = params.limit.unwrap_or(10);
+    let suggestions = state.suggest(&params.prefix, limit, caller.roots.as_deref())?;
+    Ok(Json(suggestions))
+}
+
+#[derive(Serialize)]
+struct Status {
+    version: &'static str,
+    paths: Vec<String>,
+    /// 统计包含全部根目录，只对可访问全部根目录的调用方返回
+    stats: Option<IndexStats>,
+}
+
+/// 可访问的索引路径及索引统计
+async fn status(State(state): State<SearchState>, caller: Caller) -> ApiResult<Status> {
+    let config = Config::load().await?;
+    let (paths, stats) = match caller.roots {
+        Some(roots) => (roots, None),
+        None => (config.paths, Some(state.stats()?)),
+    };
+    Ok(Json(Status {
+        version: env!("CARGO_PKG_VERSION"),
+        paths,
+        stats,
+    }))
+}
+
+/// 根据 id 读取文档，包含全文
+async fn document(
+    State(state): State<SearchState>,
+    caller: Caller,
+    Path(id): Path<String>,
+) -> ApiResult<Docx> {
+    match state.get_document(&id, caller.roots.as_deref())? {
+        Some(docx) => Ok(Json(docx)),
+        None => Err(ApiError(
+            StatusCode::NOT_FOUND,
+            CommandError::new("search.DocumentNotFound", format!("未找到文档：{id}"))
+                .with_detail("id", &id),
+        )),
+    }
+}
+
+#[derive(Deserialize)]
+struct PageParams {
+    #[serde(default)]
+    offset: usize,
+    #[serde(default = "default_limit")]
+    limit: usize,
+}
+
+/// 检索相似文档，支持分页，与 similar_doc_file 相同
+async fn similar(
+    State(state): State<SearchState>,
+    caller: Caller,
+    Path(id): Path<String>,
+    Query(params): Query<PageParams>,
+) -> ApiResult<SearchFruit> {
+    let roots = caller.roots.as_deref();
+    let fruit = state.similar(&id, params.offset, params.limit, roots)?;
+    Ok(Json(fruit))
+}
+
+/// 下载原文件，网页版以此代替打开文件；只能下载已索引的文件
+async fn download(
+    State(state): State<SearchState>,
+    caller: Caller,
+    Path(id): Path<String>,
+) -> core::result::Result<Response, ApiError> {
+    let Some(docx) = state.get_document(&id, caller.roots.as_deref())? else {
+        return Err(ApiError(
+            StatusCode::NOT_FOUND,
+            CommandError::new("search.DocumentNotFound", format!("未找到文档：{id}"))
+                .with_detail("id", &id),
+        ));
+    };
+    let bytes = tokio::fs::read(&docx.path).await.map_err(|e| {
+        ApiError(
+            StatusCode::NOT_FOUND,
+            CommandError::new("server.ReadFile", format!("{}\n{e}", docx.path))
+                .with_detail("path", &docx.path),
+        )
+    })?;
+    let disposition = format!(
+        "attachment; filename*=UTF-8''{}",
+        percent_encode(&docx.name)
+    );
+    Ok((
+        [
+            (header::CONTENT_TYPE, "application/octet-stream".to_string()),
+            (header::CONTENT_DISPOSITION, disposition),
+        ],
+        bytes,
+    )
+        .into_response())
+}
+
+/// 按 RFC 5987 编码文件名，保留字母、数字及少数符号
+fn percent_encode(text: &str) -> String {
+    text.bytes()
+        .map(|b| match b {
+            b'a'..=b'z' | b'A'..=b'Z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{b:02X}"),
+        })
+        .collect()
+}
+
+type ApiResult<T> = core::result::Result<Json<T>, ApiError>;
+
+/// 接口错误，以 JSON 返回错误信息
+#[derive(Debug)]
+pub(crate) struct ApiError(StatusCode, CommandError);
+
+/// 由请求内容引起的错误，返回 4xx 而非 500
+const CLIENT_ERRORS: &[(&str, StatusCode)] = &[
+    ("search.SearchParser", StatusCode::BAD_REQUEST),
+    ("search.RegexPattern", StatusCode::BAD_REQUEST),
+    ("search.FolderQuery", StatusCode::BAD_REQUEST),
+    ("search.InvalidLimit", StatusCode::BAD_REQUEST),
+    ("search.DocumentNotFound", StatusCode::NOT_FOUND),
+];
+
+impl<E: Into<CommandError>> From<E> for ApiError {
+    fn from(e: E) -> Self {
+        let error = e.into();
+        let status = CLIENT_ERRORS
+            .iter()
+            .find(|(code, _)| *code == error.code)
+            .map_or(StatusCode::INTERNAL_SERVER_ERROR, |(_, status)| *status);
+        Self(status, error)
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let ApiError(status, error) = self;
+        if status.is_server_error() {
+            error!("{error}");
+        }
+        (status, Json(error)).into_response()
+    }
+}
+
+type Result<T> = core::result::Result<T, Error>;
+
+#[derive(Debug, Snafu, CommandError)]
+pub enum Error {
+    #[snafu(display("无法监听地址：{address}"), context(suffix(false)))]
+    #[command_error(code = "server.Bind")]
+    Bind {
+        source: hyper::Error,
+        address: SocketAddr,
+    },
+
+    #[snafu(display("HTTP 接口异常退出"), context(suffix(false)))]
+    #[command_error(code = "server.Serve")]
+    Serve { source: hyper::Error },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn client_errors_are_not_server_errors() {
+        let ApiError(status, _) = ApiError::from(search::Error::InvalidLimit);
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        let ApiError(status, _) = ApiError::from(search::Error::DocumentNotFound {
+            id: "1".to_string(),
+        });
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        let ApiError(status, _) = ApiError::from(search::Error::TokenizerNotFound {
+            name: "default".to_string(),
+        });
+        assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+    }
+}
@@ -0,0 +1,88 @@
+//! OpenSearch 描述文档及搜索建议，便于在浏览器中添加为搜索引擎，直接在地址栏中检索
+use axum::{
+    extract::{Query, State},
+    http::header,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::Deserialize;
+use word_index_core::SearchState;
+
+use crate::auth::Caller;
+use crate::ApiError;
+
+/// 地址栏中最多显示的建议数
+const SUGGEST_LIMIT: usize = 8;
+
+#[derive(Deserialize)]
+pub struct DescriptionParams {
+    token: Option<String>,
+}
+
+/// 描述文档，检索地址为网页版首页；浏览器无法设置请求头，访问令牌以参数附加在地址中
+/// 地址使用监听的 127.0.0.1 及端口，不使用请求的 Host，避免被伪造的 Host 将令牌发往其他站点
+pub async fn description(port: u16, Query(params): Query<DescriptionParams>) -> Response {
+    let base = format!("http://127.0.0.1:{port}");
+    let token = params
+        .token
+        .map(|token| format!("&amp;token={}", crate::percent_encode(&token)))
+        .unwrap_or_default();
+    let body = format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<OpenSearchDescription xmlns="http://a9.com/-/spec/opensearch/1.1/">
+  <ShortName>word-index</ShortName>
+  <Description>检索 word-index 索引的文档</Description>
+  <InputEncoding>UTF-8</InputEncoding>
+  <Url type="text/html" method="get" template="{base}/?q={{searchTerms}}{token}"/>
+  <Url type="application/x-suggestions+json" method="get" template="{base}/api/opensearch/suggest?q={{searchTerms}}{token}"/>
+</OpenSearchDescription>
+"#,
+        base = xml_escape(&base),
+    );
+    (
+        [(
+            header::CONTENT_TYPE,
+            "application/opensearchdescription+xml",
+        )],
+        body,
+    )
+        .into_response()
+}
+
+#[derive(Deserialize)]
+pub struct SuggestionsParams {
+    q: String,
+}
+
+/// OpenSearch 建议格式：[检索词, [建议...]]，与网页版相同，补全最后一个词
+pub async fn suggestions(
+    State(state): State<SearchState>,
+    caller: Caller,
+    Query(params): Query<SuggestionsParams>,
+) -> Result<Response, ApiError> {
+    let mut words: Vec<&str> = params.q.split_whitespace().collect();
+    let completions = match words.pop() {
+        Some(prefix) if !params.q.ends_with(char::is_whitespace) => state
+            .suggest(prefix, SUGGEST_LIMIT, caller.roots.as_deref())?
+            .into_iter()
+            .map(|s| {
+                let mut completed = words.clone();
+                completed.push(&s.text);
+                completed.join(" ")
+            })
+            .collect(),
+        _ => Vec::new(),
+    };
+    Ok((
+        [(header::CONTENT_TYPE, "application/x-suggestions+json")],
+        Json((params.q, completions)),
+    )
+        .into_response())
+}
+
+fn xml_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
@@ -3,22 +3,23 @@
     windows_subsystem = "windows"
 )]
 
-use crate::config::Config;
-use command_result::CommandError;
-use command_result::Result;
-use search::SearchState;
-use structs::SearchFruit;
+use std::path::PathBuf;
+
+use tantivy::query::Explanation;
+use tauri::api::notification::Notification;
 use tauri::async_runtime;
+use tauri::AppHandle;
 use tauri::Manager;
 use tauri::State;
 use time::{macros::format_description, UtcOffset};
-use tracing::{info, instrument};
+use tracing::{error, info, instrument};
 use tracing_subscriber::fmt::time::OffsetTime;
-
-mod command_result;
-mod config;
-mod search;
-mod structs;
+use word_index_core::search;
+use word_index_core::structs::{
+    DuplicateCluster, SavedQuery, SavedSearch, SearchAlert, SearchFruit, SearchOptions,
+    StartupStatus, Suggestion,
+};
+use word_index_core::{CommandError, Config, Result, SearchState};
 
 fn main() {
     let file_appender = tracing_appender::rolling::never(".", "word-index.log");
@@ -36,15 +37,44 @@ fn main() {
 
     tauri::Builder::default()
         .setup(|app| {
-            let state = SearchState::new();
+            let config = async_runtime::block_on(Config::load())?;
+            let handle = app.handle();
+            // 窗口、HTTP 服务的每次索引提交后都会检查保存的检索
+            let state =
+                SearchState::new(&config).on_alerts(move |alerts| notify_alerts(&handle, alerts));
+            if config.server.enabled {
+                // 与窗口共用 SearchState，两者的索引与检索结果保持一致
+                let state = state.clone();
+                let port = config.server.port;
+                let web_root = config.server.web_root.clone().map(PathBuf::from);
+                async_runtime::spawn(async move {
+                    let _ = word_index_server::serve(state, port, web_root)
+                        .await
+                        .inspect_err(|e| error!("{e}"));
+                });
+            }
+            if state.startup.rebuilt && !config.paths.is_empty() {
+                async_runtime::spawn(rebuild_index(app.handle(), state.clone(), config));
+            }
             app.manage(state);
             Ok(())
         })
         .invoke_handler(tauri::generate_handler![
             index_doc_file,
+            reindex,
             search_doc_file,
+            suggest,
+            similar_doc_file,
+            explain_doc_file,
+            duplicate_report,
             save_path,
             get_paths,
+            get_history,
+            clear_history,
+            get_saved_searches,
+            save_search,
+            delete_saved_search,
+            get_startup_status,
             open_file,
         ])
         .run(tauri::generate_context!())
@@ -61,10 +91,57 @@ async fn index_doc_file(dir_path: String, state: State<'_, SearchState>) -> Resu
         handle.block_on(async { search.index(dir_path).await })
     })
     .await
-    .map_err(|e| CommandError(e.to_string()))?;
+    .map_err(|e| CommandError::new("app.Join", e.to_string()))?;
+    Ok(())
+}
+
+/// 重新加载分词词典，并重建所有路径的索引
+#[tauri::command]
+async fn reindex(state: State<'_, SearchState>) -> Result<()> {
+    let config = Config::load().await?;
+    let handle = async_runtime::TokioHandle::current();
+    let search = state.inner().clone();
+    async_runtime::spawn_blocking(move || handle.block_on(async { search.reindex(&config).await }))
+        .await
+        .map_err(|e| CommandError::new("app.Join", e.to_string()))??;
     Ok(())
 }
 
+/// 索引结构变更后旧索引已清空，在后台为所有路径重新创建索引，完成后发送 index-rebuilt 事件
+async fn rebuild_index(app: AppHandle, state: SearchState, config: Config) {
+    info!("索引结构已变更，重新创建索引");
+    let handle = async_runtime::TokioHandle::current();
+    let result: Result<()> = async_runtime::spawn_blocking(move || {
+        handle.block_on(async { state.reindex(&config).await })
+    })
+    .await
+    .map_err(|e| CommandError::new("app.Join", e.to_string()))
+    .and_then(|result| result.map_err(CommandError::from));
+    if let Err(e) = &result {
+        error!("{e}");
+    }
+    let _ = app
+        .emit_all("index-rebuilt", result.err())
+        .inspect_err(|e| error!("{e}"));
+}
+
+/// 保存的检索有新的匹配文档时，发送桌面通知及 search-alerts 事件
+fn notify_alerts(app: &AppHandle, alerts: Vec<SearchAlert>) {
+    let body = alerts
+        .iter()
+        .map(|alert| format!("{}：{} 个", alert.name, alert.total))
+        .collect::<Vec<_>>()
+        .join("\n");
+    let _ = Notification::new(&app.config().tauri.bundle.identifier)
+        .title("保存的检索有新文档")
+        .body(body)
+        .show()
+        .inspect_err(|e| error!("{e}"));
+    let _ = app
+        .emit_all("search-alerts", &alerts)
+        .inspect_err(|e| error!("{e}"));
+}
+
 /// 搜索文件，支持分页
 #[tauri::command]
 async fn search_doc_file(
@@ -72,27 +149,165 @@ async fn search_doc_file(
     offset: usize,
     limit: usize,
     classes: Option<Vec<String>>,
+    options: Option<SearchOptions>,
+    state: State<'_, SearchState>,
+) -> Result<SearchFruit> {
+    let config = Config::load().await?;
+    let options = options.unwrap_or_default();
+    let fruit = state.search(
+        keyword.clone(),
+        offset,
+        limit,
+        classes.clone(),
+        &options,
+        &config,
+    )?;
+    // 只记录首页的检索，翻页不重复记录
+    if offset == 0 && !keyword.trim().is_empty() {
+        let query = SavedQuery {
+            keyword,
+            classes,
+            options,
+        };
+        let _ = Config::update(|config| config.push_history(query))
+            .await
+            .inspect_err(|e| error!("{e}"));
+    }
+    Ok(fruit)
+}
+
+/// 解释文档在本次检索中的评分，用于排查排序问题
+#[tauri::command]
+async fn explain_doc_file(
+    keyword: String,
+    id: String,
+    classes: Option<Vec<String>>,
+    options: Option<SearchOptions>,
+    state: State<'_, SearchState>,
+) -> Result<Explanation> {
+    let config = Config::load().await?;
+    let options = options.unwrap_or_default();
+    let explanation = state.explain(keyword, &id, classes, &options, &config)?;
+    Ok(explanation)
+}
+
+/// 检索相似文档，支持分页
+#[tauri::command]
+async fn similar_doc_file(
+    id: String,
+    offset: usize,
+    limit: usize,
     state: State<'_, SearchState>,
 ) -> Result<SearchFruit> {
-    let fruit = state.search(keyword, offset, limit, classes)?;
+    let fruit = state.similar(&id, offset, limit, None)?;
     Ok(fruit)
 }
 
+/// 列出内容相同或近似的文档
+#[tauri::command]
+async fn duplicate_report(state: State<'_, SearchState>) -> Result<Vec<DuplicateCluster>> {
+    let clusters = state.duplicates()?;
+    Ok(clusters)
+}
+
+/// 检索词补全
+#[tauri::command]
+async fn suggest(
+    prefix: String,
+    limit: Option<usize>,
+    state: State<'_, SearchState>,
+) -> Result<Vec<Suggestion>> {
+    let suggestions = state.suggest(&prefix, limit.unwrap_or(10), None)?;
+    Ok(suggestions)
+}
+
 /// 保存索引路径
 #[tauri::command]
 #[instrument]
 async fn save_path(path: String) -> Result<()> {
     info!("save_path");
-    let mut config = Config::load().await?;
-    if config.paths.contains(&path) {
-        return Err(CommandError(format!("{path}\n索引路径已存在！")));
+    Config::update(|config| {
+        if config.paths.contains(&path) {
+            return Err(
+                CommandError::new("app.PathExists", format!("{path}\n索引路径已存在！"))
+                    .with_detail("path", &path),
+            );
+        }
+        config.paths.push(path);
+        Ok(())
+    })
+    .await?
+}
+
+/// 读取检索历史
+#[tauri::command]
+#[instrument]
+async fn get_history() -> Result<Vec<SavedQuery>> {
+    info!("get_history");
+    let config = Config::load().await?;
+    Ok(config.history)
+}
+
+/// 清空检索历史
+#[tauri::command]
+#[instrument]
+async fn clear_history() -> Result<()> {
+    info!("clear_history");
+    Config::update(|config| config.history.clear()).await?;
+    Ok(())
+}
+
+/// 读取保存的检索
+#[tauri::command]
+#[instrument]
+async fn get_saved_searches() -> Result<Vec<SavedSearch>> {
+    info!("get_saved_searches");
+    let config = Config::load().await?;
+    Ok(config.saved_searches)
+}
+
+/// 保存检索，同名的检索将被覆盖
+#[tauri::command]
+#[instrument]
+async fn save_search(
+    name: String,
+    keyword: String,
+    classes: Option<Vec<String>>,
+    options: Option<SearchOptions>,
+) -> Result<()> {
+    info!("save_search");
+    let name = name.trim().to_string();
+    if name.is_empty() {
+        return Err(CommandError::new("app.EmptySearchName", "请输入检索名称！"));
     }
-    config.paths.push(path);
-    config.save().await?;
+    let saved = SavedSearch {
+        name,
+        query: SavedQuery {
+            keyword,
+            classes,
+            options: options.unwrap_or_default(),
+        },
+        last_checked: search::now(),
+    };
+    Config::update(|config| config.save_search(saved)).await?;
+    Ok(())
+}
 
+/// 删除保存的检索
+#[tauri::command]
+#[instrument]
+async fn delete_saved_search(name: String) -> Result<()> {
+    info!("delete_saved_search");
+    Config::update(|config| config.delete_saved_search(&name)).await?;
     Ok(())
 }
 
+/// 启动时需告知用户的状态，如索引结构变更后正在重建索引
+#[tauri::command]
+fn get_startup_status(state: State<'_, SearchState>) -> StartupStatus {
+    state.startup.clone()
+}
+
 /// 读取索引路径
 #[tauri::command]
 #[instrument]
@@ -115,11 +330,12 @@ fn open_file_by_default_program(path: &str) -> Result<()> {
 
     Command::new("rundll32")
         .args(["url.dll", "FileProtocolHandler", path])
-        .output()?;
+        .output()
+        .map_err(|e| CommandError::new("app.OpenFile", e.to_string()).with_detail("path", path))?;
     Ok(())
 }
 
 #[cfg(not(windows))]
 fn open_file_by_default_program(_path: &str) -> Result<()> {
-    Err(CommandError(String::from("未适配！")))
+    Err(CommandError::new("app.Unsupported", "未适配！"))
 }
@@ -0,0 +1,173 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use snafu::prelude::*;
+use tokio::io::{stdin, stdout, AsyncBufReadExt, AsyncWriteExt, BufReader};
+use word_index_core::CommandError;
+use word_index_macros::CommandError;
+
+pub const PARSE_ERROR: i64 = -32700;
+pub const METHOD_NOT_FOUND: i64 = -32601;
+pub const INVALID_PARAMS: i64 = -32602;
+pub const INTERNAL_ERROR: i64 = -32603;
+
+/// 请求，没有 id 的是通知，无需响应
+#[derive(Deserialize, Debug)]
+pub struct Request {
+    pub id: Option<Value>,
+    pub method: String,
+    #[serde(default)]
+    pub params: Value,
+}
+
+#[derive(Serialize, Debug)]
+pub struct Response {
+    jsonrpc: &'static str,
+    id: Value,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    result: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<RpcError>,
+}
+
+impl Response {
+    pub fn new(id: Value, result: core::result::Result<Value, RpcError>) -> Self {
+        let (result, error) = match result {
+            Ok(result) => (Some(result), None),
+            Err(error) => (None, Some(error)),
+        };
+        Self {
+            jsonrpc: "2.0",
+            id,
+            result,
+            error,
+        }
+    }
+}
+
+#[derive(Serialize, Debug)]
+pub struct RpcError {
+    pub code: i64,
+    pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data: Option<Value>,
+}
+
+impl RpcError {
+    pub fn new(code: i64, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+            data: None,
+        }
+    }
+}
+
+/// 由请求参数引起的错误码，返回 INVALID_PARAMS 而非 INTERNAL_ERROR
+const INVALID_PARAMS_ERRORS: &[&str] = &[
+    "search.InvalidLimit",
+    "search.SearchParser",
+    "search.RegexPattern",
+    "search.FolderQuery",
+];
+
+/// 是否为请求参数引起的错误
+pub fn is_invalid_params(e: &CommandError) -> bool {
+    INVALID_PARAMS_ERRORS.contains(&e.code.as_str())
+}
+
+/// 错误信息之外，以 data 返回完整的 CommandError，便于调用方按错误码处理
+pub fn command_error(e: impl Into<CommandError>) -> RpcError {
+    let e = e.into();
+    RpcError {
+        code: if is_invalid_params(&e) {
+            INVALID_PARAMS
+        } else {
+            INTERNAL_ERROR
+        },
+        message: e.message.clone(),
+        data: serde_json::to_value(&e).ok(),
+    }
+}
+
+/// 处理请求的方法
+// 请求在当前任务中逐个处理，不要求返回的 Future 实现 Send
+#[allow(async_fn_in_trait)]
+pub trait Handler {
+    async fn handle(&self, method: &str, params: Value) -> core::result::Result<Value, RpcError>;
+}
+
+/// 解析请求参数，失败时返回 INVALID_PARAMS
+pub fn params<T: serde::de::DeserializeOwned>(params: Value) -> core::result::Result<T, RpcError> {
+    let params = if params.is_null() {
+        Value::Object(Default::default())
+    } else {
+        params
+    };
+    serde_json::from_value(params).map_err(|e| RpcError::new(INVALID_PARAMS, e.to_string()))
+}
+
+/// 逐行读取标准输入中的请求，将响应逐行写入标准输出，直到标准输入关闭
+pub async fn serve(handler: &impl Handler) -> Result<()> {
+    let mut lines = BufReader::new(stdin()).lines();
+    let mut stdout = stdout();
+    while let Some(line) = lines.next_line().await.context(ReadStdin)? {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let response = match serde_json::from_str::<Request>(&line) {
+            Ok(request) => {
+                let result = handler.handle(&request.method, request.params).await;
+                request.id.map(|id| Response::new(id, result))
+            }
+            Err(e) => Some(Response::new(
+                Value::Null,
+                Err(RpcError::new(PARSE_ERROR, e.to_string())),
+            )),
+        };
+        if let Some(response) = response {
+            let mut message = serde_json::to_string(&response).context(EncodeResponse)?;
+            message.push('\n');
+            stdout
+                .write_all(message.as_bytes())
+                .await
+                .context(WriteStdout)?;
+            stdout.flush().await.context(WriteStdout)?;
+        }
+    }
+    Ok(())
+}
+
+type Result<T> = core::result::Result<T, Error>;
+
+#[derive(Debug, Snafu, CommandError)]
+pub enum Error {
+    #[snafu(display("无法读取标准输入"), context(suffix(false)))]
+    #[command_error(code = "jsonrpc.ReadStdin")]
+    ReadStdin { source: std::io::Error },
+
+    #[snafu(display("无法写入标准输出"), context(suffix(false)))]
+    #[command_error(code = "jsonrpc.WriteStdout")]
+    WriteStdout { source: std::io::Error },
+
+    #[snafu(display("无法编码响应"), context(suffix(false)))]
+    #[command_error(code = "jsonrpc.EncodeResponse")]
+    EncodeResponse { source: serde_json::Error },
+}
+
+#[cfg(test)]
+mod tests {
+    use word_index_core::search;
+
+    use super::*;
+
+    #[test]
+    fn client_errors_are_invalid_params() {
+        let error = command_error(search::Error::InvalidLimit);
+        assert_eq!(error.code, INVALID_PARAMS);
+        assert_eq!(error.data.unwrap()["code"], "search.InvalidLimit");
+        let error = command_error(search::Error::DocumentNotFound {
+            id: "1".to_string(),
+        });
+        assert_eq!(error.code, INTERNAL_ERROR);
+    }
+}
@@ -0,0 +1,5 @@
+//! 基于标准输入输出、按行分隔的 JSON-RPC 协议，无需网络即可供本地程序调用
+//! 标准输出只用于协议消息，日志不得写入标准输出
+pub mod jsonrpc;
+pub mod mcp;
+pub mod rpc;
@@ -0,0 +1,193 @@
+//! Model Context Protocol 服务，提供 search、get_document、list_roots 工具，便于本地 AI 助手检索文档
+use serde::Deserialize;
+use serde_json::{json, Value};
+use word_index_core::structs::{default_limit, SearchHit, SearchOptions};
+use word_index_core::{CommandError, Config, SearchState};
+
+use crate::jsonrpc::{self, Handler, RpcError, INVALID_PARAMS, METHOD_NOT_FOUND};
+
+const PROTOCOL_VERSION: &str = "2024-11-05";
+
+/// 检索结果中每个文档的内容摘要长度（字符）
+const SNIPPET_CHARS: usize = 300;
+
+pub struct Mcp {
+    state: SearchState,
+}
+
+impl Mcp {
+    pub fn new(state: SearchState) -> Self {
+        Self { state }
+    }
+
+    /// 在标准输入输出上提供服务，直到标准输入关闭
+    pub async fn serve(&self) -> Result<(), jsonrpc::Error> {
+        jsonrpc::serve(self).await
+    }
+
+    async fn call_tool(&self, name: &str, arguments: Value) -> Result<Value, ToolError> {
+        match name {
+            "search" => {
+                let args: SearchArgs = jsonrpc::params(arguments).map_err(ToolError::Rpc)?;
+                let config = Config::load().await?;
+                let classes = (!args.classes.is_empty()).then_some(args.classes);
+                let fruit = self.state.search(
+                    args.query,
+                    args.offset,
+                    args.limit,
+                    classes,
+                    &SearchOptions::default(),
+                    &config,
+                )?;
+                let results: Vec<Value> = fruit.results.iter().map(summary).collect();
+                Ok(json!({ "total": fruit.total, "results": results }))
+            }
+            "get_document" => {
+                let args: GetDocumentArgs = jsonrpc::params(arguments).map_err(ToolError::Rpc)?;
+                match self.state.get_document(&args.id, None)? {
+                    Some(docx) => Ok(json!({
+                        "id": docx.id,
+                        "name": docx.name,
+                        "path": docx.path,
+                        "timestamp": docx.timestamp,
+                        "content": docx.content,
+                    })),
+                    None => Err(ToolError::Failed(format!("未找到文档：{}", args.id))),
+                }
+            }
+            "list_roots" => {
+                let config = Config::load().await?;
+                Ok(json!({ "roots": config.paths }))
+            }
+            _ => Err(ToolError::Rpc(RpcError::new(
+                INVALID_PARAMS,
+                format!("未知的工具：{name}"),
+            ))),
+        }
+    }
+}
+
+impl Handler for Mcp {
+    async fn handle(&self, method: &str, params: Value) -> Result<Value, RpcError> {
+        match method {
+            "initialize" => Ok(json!({
+                "protocolVersion": PROTOCOL_VERSION,
+                "capabilities": { "tools": {} },
+                "serverInfo": { "name": "word-index", "version": env!("CARGO_PKG_VERSION") },
+            })),
+            "ping" => Ok(json!({})),
+            "tools/list" => Ok(json!({ "tools": tools() })),
+            "tools/call" => {
+                let call: ToolCall = jsonrpc::params(params)?;
+                // 工具执行失败时以 isError 告知调用方，而不是返回协议错误
+                match self.call_tool(&call.name, call.arguments).await {
+                    Ok(result) => Ok(json!({
+                        "content": [{ "type": "text", "text": result.to_string() }],
+                        "isError": false,
+                    })),
+                    Err(ToolError::Failed(message)) => Ok(json!({
+                        "content": [{ "type": "text", "text": message }],
+                        "isError": true,
+                    })),
+                    Err(ToolError::Rpc(e)) => Err(e),
+                }
+            }
+            _ if method.starts_with("notifications/") => Ok(Value::Null),
+            _ => Err(RpcError::new(
+                METHOD_NOT_FOUND,
+                format!("未知的方法：{method}"),
+            )),
+        }
+    }
+}
+
+fn tools() -> Value {
+    json!([
+        {
+            "name": "search",
+            "description": "检索已索引的文档，返回文档 id、名称、路径及内容摘要。支持 tantivy 检索语法，如 name:设计 AND 数据库",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "query": { "type": "string", "description": "检索关键字" },
+                    "classes": {
+                        "type": "array",
+                        "items": { "type": "string" },
+                        "description": "文档类型，如 docx、md、txt、sql，为空时不限"
+                    },
+                    "limit": { "type": "integer", "minimum": 1, "description": "返回的文档数，默认 10" },
+                    "offset": { "type": "integer", "description": "跳过的文档数，默认 0" }
+                },
+                "required": ["query"]
+            }
+        },
+        {
+            "name": "get_document",
+            "description": "根据 search 返回的 id 读取文档全文",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "id": { "type": "string", "description": "文档 id" }
+                },
+                "required": ["id"]
+            }
+        },
+        {
+            "name": "list_roots",
+            "description": "列出已索引的根目录",
+            "inputSchema": { "type": "object", "properties": {} }
+        }
+    ])
+}
+
+fn summary(hit: &SearchHit) -> Value {
+    let doc = &hit.doc;
+    let snippet: String = doc.content.chars().take(SNIPPET_CHARS).collect();
+    json!({
+        "id": doc.id,
+        "name": doc.name,
+        "path": doc.path,
+        "timestamp": doc.timestamp,
+        "snippet": snippet,
+    })
+}
+
+#[derive(Deserialize)]
+struct ToolCall {
+    name: String,
+    #[serde(default)]
+    arguments: Value,
+}
+
+#[derive(Deserialize)]
+struct SearchArgs {
+    query: String,
+    #[serde(default)]
+    classes: Vec<String>,
+    #[serde(default = "default_limit")]
+    limit: usize,
+    #[serde(default)]
+    offset: usize,
+}
+
+#[derive(Deserialize)]
+struct GetDocumentArgs {
+    id: String,
+}
+
+/// 工具执行失败（返回给调用方）或参数错误（返回协议错误，如 limit 为 0、检索语句有误）
+enum ToolError {
+    Failed(String),
+    Rpc(RpcError),
+}
+
+impl<E: Into<CommandError>> From<E> for ToolError {
+    fn from(e: E) -> Self {
+        let e = e.into();
+        if jsonrpc::is_invalid_params(&e) {
+            Self::Rpc(jsonrpc::command_error(e))
+        } else {
+            Self::Failed(e.to_string())
+        }
+    }
+}
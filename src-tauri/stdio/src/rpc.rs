@@ -0,0 +1,121 @@
+//! 供编辑器插件以子进程调用的 JSON-RPC 服务，提供 search、suggest、open、status 方法
+use serde::Deserialize;
+use serde_json::{json, Value};
+use tokio::process::Command;
+use word_index_core::structs::SearchRequest;
+use word_index_core::{search, Config, SearchState};
+
+use crate::jsonrpc::{self, command_error, Handler, RpcError, INTERNAL_ERROR, METHOD_NOT_FOUND};
+
+pub struct Rpc {
+    state: SearchState,
+}
+
+impl Rpc {
+    pub fn new(state: SearchState) -> Self {
+        Self { state }
+    }
+
+    /// 在标准输入输出上提供服务，直到标准输入关闭
+    pub async fn serve(&self) -> Result<(), jsonrpc::Error> {
+        jsonrpc::serve(self).await
+    }
+}
+
+impl Handler for Rpc {
+    async fn handle(&self, method: &str, params: Value) -> Result<Value, RpcError> {
+        match method {
+            // 与 search_doc_file 相同
+            "search" => {
+                let params: SearchRequest = jsonrpc::params(params)?;
+                let config = Config::load().await.map_err(command_error)?;
+                let options = params.options.unwrap_or_default();
+                let fruit = self
+                    .state
+                    .search(
+                        params.keyword,
+                        params.offset,
+                        params.limit,
+                        params.classes,
+                        &options,
+                        &config,
+                    )
+                    .map_err(command_error)?;
+                to_value(fruit)
+            }
+            // 与 suggest 相同
+            "suggest" => {
+                let params: SuggestParams = jsonrpc::params(params)?;
+                let suggestions = self
+                    .state
+                    .suggest(&params.prefix, params.limit.unwrap_or(10), None)
+                    .map_err(command_error)?;
+                to_value(suggestions)
+            }
+            // 以系统默认程序打开已索引的文档
+            "open" => {
+                let params: OpenParams = jsonrpc::params(params)?;
+                let docx = self
+                    .state
+                    .get_document(&params.id, None)
+                    .map_err(command_error)?
+                    .ok_or_else(|| {
+                        command_error(search::Error::DocumentNotFound { id: params.id })
+                    })?;
+                open_by_default_program(&docx.path)
+                    .await
+                    .map_err(|e| RpcError::new(INTERNAL_ERROR, format!("{}\n{e}", docx.path)))?;
+                Ok(json!({ "path": docx.path }))
+            }
+            "status" => {
+                let config = Config::load().await.map_err(command_error)?;
+                let stats = self.state.stats().map_err(command_error)?;
+                Ok(json!({
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "paths": config.paths,
+                    "stats": stats,
+                }))
+            }
+            _ => Err(RpcError::new(
+                METHOD_NOT_FOUND,
+                format!("未知的方法：{method}"),
+            )),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct SuggestParams {
+    prefix: String,
+    limit: Option<usize>,
+}
+
+#[derive(Deserialize)]
+struct OpenParams {
+    id: String,
+}
+
+fn to_value(value: impl serde::Serialize) -> Result<Value, RpcError> {
+    serde_json::to_value(value).map_err(|e| RpcError::new(INTERNAL_ERROR, e.to_string()))
+}
+
+#[cfg(windows)]
+async fn open_by_default_program(path: &str) -> std::io::Result<()> {
+    Command::new("rundll32")
+        .args(["url.dll,FileProtocolHandler", path])
+        .status()
+        .await?;
+    Ok(())
+}
+
+#[cfg(target_os = "macos")]
+async fn open_by_default_program(path: &str) -> std::io::Result<()> {
+    Command::new("open").arg(path).status().await?;
+    Ok(())
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+async fn open_by_default_program(path: &str) -> std::io::Result<()> {
+    Command::new("xdg-open").arg(path).status().await?;
+    Ok(())
+}
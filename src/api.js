@@ -0,0 +1,99 @@
+import { invoke as tauriInvoke } from "@tauri-apps/api/tauri";
+
+// 在桌面版中运行时调用 Tauri 命令，在浏览器中运行时调用 HTTP 接口
+export const isTauri = "__TAURI_IPC__" in window;
+
+const routes = {
+  search_doc_file: (args) => post("/api/search", args),
+  explain_doc_file: (args) => post("/api/explain", args),
+  suggest: ({ prefix, limit }) => get("/api/suggest", { prefix, limit }),
+  similar_doc_file: ({ id, offset, limit }) =>
+    get(`/api/documents/${encodeURIComponent(id)}/similar`, { offset, limit }),
+  get_paths: () => get("/api/status").then((status) => status.paths),
+};
+
+export function invoke(cmd, args = {}) {
+  if (isTauri) {
+    return tauriInvoke(cmd, args);
+  }
+  const route = routes[cmd];
+  if (!route) {
+    return Promise.reject({
+      code: "web.Unsupported",
+      message: "网页版不支持该操作！",
+    });
+  }
+  return route(args);
+}
+
+// 命令失败时返回 { code, message, details, sources }，code 为稳定的错误码，message 用于展示
+export function errorText(err) {
+  if (err && typeof err == "object") {
+    return err.message || err.code || String(err);
+  }
+  return String(err);
+}
+
+// 网页版以下载代替打开原文件，链接无法携带请求头，以参数传递访问令牌
+export function downloadUrl(id) {
+  const url = `/api/documents/${encodeURIComponent(id)}/download`;
+  const token = localStorage.getItem(TOKEN_KEY);
+  return token ? `${url}?token=${encodeURIComponent(token)}` : url;
+}
+
+const TOKEN_KEY = "word-index-token";
+
+// 从地址栏检索时（opensearch.xml），访问令牌以 token 参数附加在地址中，保存后从地址中移除
+if (!isTauri) {
+  const url = new URL(window.location.href);
+  const token = url.searchParams.get("token");
+  if (token) {
+    localStorage.setItem(TOKEN_KEY, token);
+    url.searchParams.delete("token");
+    window.history.replaceState(window.history.state, "", url);
+  }
+}
+
+function get(url, params = {}) {
+  const query = new URLSearchParams(
+    Object.entries(params).filter(([, value]) => value != null)
+  ).toString();
+  return request(query ? `${url}?${query}` : url, { method: "GET" });
+}
+
+function post(url, body) {
+  return request(url, {
+    method: "POST",
+    headers: { "Content-Type": "application/json" },
+    body: JSON.stringify(body),
+  });
+}
+
+// 与 Tauri 命令一致，失败时以结构化的错误 reject；服务端要求访问令牌时提示输入后重试
+async function request(url, init, retry = true) {
+  const token = localStorage.getItem(TOKEN_KEY);
+  const headers = { ...init.headers };
+  if (token) {
+    headers.Authorization = `Bearer ${token}`;
+  }
+  let res;
+  try {
+    res = await fetch(url, { ...init, headers });
+  } catch (e) {
+    throw { code: "http.Network", message: e.message };
+  }
+  if (res.status == 401 && retry) {
+    const input = window.prompt("请输入访问令牌");
+    if (input) {
+      localStorage.setItem(TOKEN_KEY, input.trim());
+      return request(url, init, false);
+    }
+  }
+  const data = await res.json().catch(() => null);
+  if (!res.ok) {
+    throw data && data.code
+      ? data
+      : { code: `http.${res.status}`, message: res.statusText };
+  }
+  return data;
+}